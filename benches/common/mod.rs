@@ -0,0 +1,40 @@
+use genetic::data_structures::{
+    individual::{IntegerCrossover, IntegerMutation},
+    population::GenerationParameters,
+    selection::{TieBreak, Tournament},
+    task::Task,
+};
+use genetic::fixtures;
+use rand::thread_rng;
+
+/// Random task, in which about half of the items fit into the knapsack.
+pub fn random_task(items: usize) -> Task {
+    fixtures::random_task(items, 1..100, 0.5, &mut thread_rng())
+}
+
+/// Random task, in which each item earns a profit together with `pairs` other items.
+pub fn random_task_with_interactions(items: usize, pairs: usize) -> Task {
+    let interactions = fixtures::random_interactions(items, items * pairs, &mut thread_rng());
+
+    random_task(items).with_interactions(interactions)
+}
+
+pub fn generation_parameters() -> GenerationParameters<'static> {
+    GenerationParameters {
+        tournament: Tournament {
+            size: 3,
+            with_replacement: false,
+            tie_break: TieBreak::LowestIndex,
+        },
+        crossover_rate: 0.9,
+        cutting_point: None,
+        mutation_rate: 0.01,
+        local_search: None,
+        local_search_rate: 0.0,
+        self_adaptive: false,
+        integer_mutation: IntegerMutation::Step,
+        integer_crossover: IntegerCrossover::OnePoint,
+        diversity: None,
+        population_diversity: None,
+    }
+}
@@ -0,0 +1,119 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use genetic::data_structures::{
+    individual::{as_individual, as_individual_mut, Crossover, Individual, IndividualMut, Mutate},
+    selection::{TieBreak, Tournament},
+    Float, FloatDVector, PopulationMatrix, Random,
+};
+use nalgebra::Dim;
+use rand::{
+    distributions::{Bernoulli, Distribution},
+    thread_rng, SeedableRng,
+};
+use rayon::prelude::*;
+
+const ITEMS: usize = 1000;
+const SIZES: [usize; 2] = [100, 1000];
+const TOURNAMENT: Tournament = Tournament {
+    size: 3,
+    with_replacement: false,
+    tie_break: TieBreak::LowestIndex,
+};
+
+fn random_matrix(rows: usize, cols: usize) -> PopulationMatrix {
+    let dist = Bernoulli::new(0.5).unwrap();
+    let mut rng = thread_rng();
+    PopulationMatrix::from_fn(rows, cols, |_, _| dist.sample(&mut rng) as Float)
+}
+
+// the breeding shared by both schemes, so only the storage of the individuals differs
+fn breed<'a, P>(
+    individual: &mut IndividualMut<'a>,
+    parent: P,
+    evaluation: &FloatDVector,
+    rng: &mut Random,
+) where
+    P: Fn(usize) -> Individual<'a>,
+{
+    let first = TOURNAMENT.select(evaluation, rng);
+    let second = TOURNAMENT.select(evaluation, rng);
+    individual.random_crossover(parent(first), parent(second), 0.9, rng);
+    individual.mutate(0.01, rng);
+}
+
+// the scheme replaced by the contiguous individuals of `Population`: the individuals are
+// strided rows of a matrix stored by columns, bred into the row views of an uninitialised
+// workbench collected every generation, which is swapped with the population
+#[allow(deprecated)]
+fn unsafe_workbench_generation(
+    population: &mut PopulationMatrix,
+    workbench: &mut PopulationMatrix,
+    evaluation: &FloatDVector,
+) {
+    let parents: &PopulationMatrix = population;
+    workbench
+        .row_iter_mut()
+        .collect::<Vec<_>>()
+        .par_iter_mut()
+        .for_each_init(Random::from_entropy, |rng, individual| {
+            breed(individual, |i| parents.row(i), evaluation, rng)
+        });
+
+    unsafe {
+        std::mem::swap(population.data.as_vec_mut(), workbench.data.as_vec_mut());
+    }
+}
+
+// the scheme of `Population`: each individual is a contiguous column,
+// the children are bred into chunks of the offspring matrix, which is swapped with the population
+fn contiguous_generation(
+    population: &mut PopulationMatrix,
+    offspring: &mut PopulationMatrix,
+    evaluation: &FloatDVector,
+) {
+    let cols = population.nrows();
+    let parents = population.as_slice();
+    offspring.as_mut_slice().par_chunks_mut(cols).for_each_init(
+        Random::from_entropy,
+        |rng, genes| {
+            let parent = |i: usize| as_individual(&parents[i * cols..(i + 1) * cols]);
+            breed(&mut as_individual_mut(genes), parent, evaluation, rng)
+        },
+    );
+
+    std::mem::swap(population, offspring);
+}
+
+#[allow(deprecated)]
+fn storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generation storage");
+
+    for &rows in SIZES.iter() {
+        let evaluation = FloatDVector::from_fn(rows, |row, _| row as Float + 1);
+
+        group.bench_with_input(
+            BenchmarkId::new("unsafe workbench", rows),
+            &rows,
+            |b, &rows| {
+                let mut population = random_matrix(rows, ITEMS);
+                let mut workbench: PopulationMatrix = unsafe {
+                    PopulationMatrix::new_uninitialized_generic(
+                        Dim::from_usize(rows),
+                        Dim::from_usize(ITEMS),
+                    )
+                };
+                b.iter(|| unsafe_workbench_generation(&mut population, &mut workbench, &evaluation))
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("contiguous", rows), &rows, |b, &rows| {
+            let mut population = random_matrix(ITEMS, rows);
+            let mut offspring = PopulationMatrix::zeros(ITEMS, rows);
+            b.iter(|| contiguous_generation(&mut population, &mut offspring, &evaluation))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, storage);
+criterion_main!(benches);
@@ -0,0 +1,287 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use genetic::data_structures::{
+    evaluation::EvaluationBackend,
+    individual::{Crossover, Mutate},
+    packed::PackedPopulation,
+    population::Population,
+    selection::{TieBreak, Tournament},
+    utils::{get_sparse_vec, sample_without_replacement},
+    Float, FloatDVector, PopulationMatrix, Random,
+};
+use rand::{Rng, SeedableRng};
+use rayon::iter::ParallelIterator;
+
+mod common;
+
+// (population size, number of items)
+const SHAPES: [(usize, usize); 3] = [(100, 1000), (1000, 1000), (1000, 10000)];
+
+fn shape_id(name: &str, (population_size, items): (usize, usize)) -> BenchmarkId {
+    BenchmarkId::new(name, format!("{}x{}", population_size, items))
+}
+
+fn generate_initial_population(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate initial population");
+    let mut rng = Random::from_entropy();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        group.bench_with_input(shape_id("bernoulli", shape), &shape, |b, _| {
+            b.iter(|| {
+                Population::generate_initial_population(population_size, items, 0.5, &mut rng)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn evaluate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluate");
+    let mut rng = Random::from_entropy();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        let task = common::random_task(items);
+        let population =
+            Population::generate_initial_population(population_size, items, 0.5, &mut rng);
+
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        group.bench_with_input(shape_id("knapsack", shape), &shape, |b, _| {
+            b.iter(|| population.evaluate(&task))
+        });
+    }
+
+    group.finish();
+}
+
+fn evaluation_backend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluation back-end");
+    let mut rng = Random::from_entropy();
+    let (population_size, items) = (1000, 1000);
+    let tasks = [
+        ("knapsack", common::random_task(items)),
+        (
+            "interactions",
+            common::random_task_with_interactions(items, 5),
+        ),
+    ];
+
+    group.throughput(Throughput::Elements((population_size * items) as u64));
+    for &density in [0.005, 0.01, 0.02, 0.05, 0.5].iter() {
+        let mut population =
+            Population::generate_initial_population(population_size, items, density, &mut rng);
+
+        for (name, task) in tasks.iter() {
+            for &backend in [
+                EvaluationBackend::Dense,
+                EvaluationBackend::Sparse,
+                EvaluationBackend::Packed,
+            ]
+            .iter()
+            {
+                population.set_evaluation_backend(backend);
+                group.bench_with_input(
+                    BenchmarkId::new(format!("{:?} {}", backend, name), density),
+                    &density,
+                    |b, _| b.iter(|| population.evaluate(task)),
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn packed_kernel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("packed kernel");
+    let mut rng = Random::from_entropy();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        let task = common::random_task(items);
+        let (weights, sizes, costs) = (
+            task.get_weights().as_slice(),
+            task.get_sizes().as_slice(),
+            task.get_costs().as_slice(),
+        );
+        let population =
+            Population::generate_initial_population(population_size, items, 0.5, &mut rng);
+        let matrix = PopulationMatrix::from_fn(items, population_size, |item, individual| {
+            population.individual(individual)[item]
+        });
+
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        group.bench_with_input(shape_id("pack", shape), &shape, |b, _| {
+            b.iter(|| PackedPopulation::pack(&matrix, true))
+        });
+        let packed = PackedPopulation::pack(&matrix, true);
+        group.bench_with_input(shape_id("detected", shape), &shape, |b, _| {
+            b.iter(|| packed.masked_sums(weights, sizes, costs, true))
+        });
+        group.bench_with_input(shape_id("scalar", shape), &shape, |b, _| {
+            b.iter(|| packed.masked_sums_scalar(weights, sizes, costs, true))
+        });
+    }
+
+    group.finish();
+}
+
+fn tournament(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tournament selection");
+
+    for &population_size in [100, 1000, 10000].iter() {
+        let evaluation = FloatDVector::from_fn(population_size, |row, _| row as Float);
+
+        for &size in [2, 8].iter() {
+            for &with_replacement in [false, true].iter() {
+                let tournament = Tournament {
+                    size,
+                    with_replacement,
+                    tie_break: TieBreak::Random,
+                };
+                let name = format!(
+                    "size {} {}",
+                    size,
+                    if with_replacement {
+                        "with replacement"
+                    } else {
+                        "without replacement"
+                    }
+                );
+                group.bench_with_input(
+                    BenchmarkId::new(name, population_size),
+                    &population_size,
+                    |b, _| {
+                        let mut rng = Random::from_entropy();
+                        b.iter(|| tournament.select(&evaluation, &mut rng))
+                    },
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn sampling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sample without replacement");
+
+    for &n in [1000, 10000].iter() {
+        // a tournament, a mutation with the rate of 1%, and all the indices
+        for &k in [3, n / 100, n].iter() {
+            group.bench_with_input(BenchmarkId::new(format!("k {}", k), n), &n, |b, &n| {
+                let mut rng = Random::from_entropy();
+                b.iter(|| sample_without_replacement(n, k, &mut rng))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn sparse_vec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get sparse vec");
+
+    for &n in [1000, 10000].iter() {
+        for &k in [3, n / 100, n].iter() {
+            group.bench_with_input(BenchmarkId::new(format!("k {}", k), n), &n, |b, &n| {
+                let mut rng = Random::from_entropy();
+                b.iter(|| get_sparse_vec(n, k, &mut rng))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn crossover(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crossover");
+    let mut rng = Random::from_entropy();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        let parents =
+            Population::generate_initial_population(population_size, items, 0.5, &mut rng);
+        let mut offspring = Population::from(PopulationMatrix::zeros(items, population_size));
+
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        group.bench_with_input(shape_id("random cutting point", shape), &shape, |b, _| {
+            b.iter(|| {
+                offspring
+                    .individuals_mut()
+                    .for_each_init(Random::from_entropy, |rng, mut child| {
+                        let first_parent = parents.individual(rng.gen_range(0, population_size));
+                        let second_parent = parents.individual(rng.gen_range(0, population_size));
+                        child.random_crossover(first_parent, second_parent, 0.9, rng)
+                    })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn mutate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mutate");
+    let mut rng = Random::from_entropy();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        let mut population =
+            Population::generate_initial_population(population_size, items, 0.5, &mut rng);
+
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        for &rate in [0.001, 0.01].iter() {
+            group.bench_with_input(
+                shape_id(&format!("rate {}", rate), shape),
+                &shape,
+                |b, _| {
+                    b.iter(|| {
+                        population
+                            .individuals_mut()
+                            .for_each_init(Random::from_entropy, |rng, mut individual| {
+                                individual.mutate(rate, rng)
+                            })
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn evolve_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evolve generation");
+    let mut rng = Random::from_entropy();
+    let parameters = common::generation_parameters();
+
+    for &shape in SHAPES.iter() {
+        let (population_size, items) = shape;
+        let task = common::random_task(items);
+        let mut population =
+            Population::generate_initial_population(population_size, items, 0.5, &mut rng);
+
+        group.throughput(Throughput::Elements((population_size * items) as u64));
+        group.bench_with_input(shape_id("knapsack", shape), &shape, |b, _| {
+            b.iter(|| {
+                population
+                    .evolve_generation(&task, &parameters, &mut rng)
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // the largest shapes touch 10 million genes in a single iteration
+    config = Criterion::default().sample_size(20);
+    targets = generate_initial_population, evaluate, evaluation_backend, packed_kernel, tournament, sampling, sparse_vec, crossover, mutate,
+        evolve_generation
+}
+criterion_main!(benches);
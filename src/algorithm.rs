@@ -1,26 +1,51 @@
 use crate::data_structures::{
-    algorithm_configuration::AlgorithmConfiguration as Configuration, DynamicResult,
+    algorithm_configuration::AlgorithmConfiguration as Configuration, DynamicResult, Float,
 };
 
-use crate::data_structures::{population::Population, Float};
-use crate::loader;
-use nalgebra::{DMatrix, Dim};
-use rand::{thread_rng, Rng};
-use std::path::PathBuf;
+use crate::data_structures::{
+    execution::derived_rng,
+    local_search::LocalSearch,
+    population::{GenerationParameters, Population, Rates},
+    restart::{RestartEvent, RestartPolicy},
+    schedule::{MutationSchedule, RateSchedule},
+    selection::Tournament,
+    solution::Solution,
+    statistics::GenerationStatistics,
+    task::Task,
+};
+use rand::Rng;
 
-pub fn evolve(config: &Configuration) -> DynamicResult<Vec<Float>> {
-    let task = loader::read(
-        config
-            .input_file
-            .as_ref()
-            .unwrap_or(&PathBuf::from("tasks.csv")),
-    )?;
+/// Outcome of a single run of the algorithm.
+pub struct Evolution {
+    pub statistics: Vec<GenerationStatistics>,
+    /// items selected by the best individual found during the run
+    pub best: Option<Solution>,
+    /// fitness of the best individual found during the run
+    pub best_fitness: Float,
+    /// seed of the random number generator, which repeats the run
+    pub seed: u64,
+    /// restarts of the population, in the order of the generations
+    pub restarts: Vec<RestartEvent>,
+}
+
+pub fn evolve(config: &Configuration, task: &Task) -> DynamicResult<Evolution> {
+    let seed = config.execution.seed();
+    let mut rand = derived_rng(seed, 0);
+
+    let ones_probability = config.density.ones_probability(task)?;
     let mut population = Population::generate_initial_population(
         config.population_size,
         task.number_of_objects,
-        config.density,
+        ones_probability,
+        &mut rand,
     );
-    let mut rand = thread_rng();
+    population.set_parallel(!config.execution.sequential);
+    population.set_evaluation_backend(config.execution.evaluation);
+    population.enable_fitness_cache(config.fitness_cache);
+    if let Some(gene_bounds) = task.get_gene_bounds() {
+        population.draw_copies(gene_bounds, &mut rand);
+    }
+    population.repair(task.get_constraints(), &mut rand);
 
     let cutting_point =
         if config.cutting_point < task.number_of_objects || config.use_random_cutting_point {
@@ -32,42 +57,170 @@ pub fn evolve(config: &Configuration) -> DynamicResult<Vec<Float>> {
             return Err("crossover barrier is set to too big number".into());
         };
 
-    // create a new matrix that will be used as workbench for creating a new population.
-    // this way, I can reduce the number of allocation of new vectors, what takes a lot of time,
-    // it can be done, as we are not interested in collecting all the generated generations
-    let mut workbench: DMatrix<Float> = unsafe {
-        DMatrix::new_uninitialized_generic(
-            Dim::from_usize(population.rows()),
-            Dim::from_usize(population.cols()),
-        )
+    let tournament = Tournament {
+        size: config.tournament_size,
+        with_replacement: config.tournament_replacement,
+        tie_break: config.tie_break,
     };
+    tournament.validate(config.population_size)?;
 
-    let results: DynamicResult<Vec<Float>> = if config.use_random_cutting_point {
-        (0..config.iterations)
-            .map(|_| {
-                population.evolve_generation_with_random_barrier(
-                    &task,
-                    config.tournament_size,
-                    config.crossover_rate,
-                    config.mutation_rate,
-                    &mut workbench,
-                )
-            })
-            .collect()
-    } else {
-        (0..config.iterations)
-            .map(|_| {
-                population.evolve_generation(
-                    &task,
-                    config.tournament_size,
-                    config.crossover_rate,
-                    cutting_point,
-                    config.mutation_rate,
-                    &mut workbench,
-                )
-            })
-            .collect()
-    };
+    if !(0f64..=1f64).contains(&config.local_search_rate) {
+        return Err("local search rate has to be in range [0, 1]".into());
+    }
+    if config.local_search.is_some() && task.get_gene_bounds().is_some() {
+        return Err(
+            "local search supports only a single knapsack with items selected at most once".into(),
+        );
+    }
+    let local_search = config
+        .local_search
+        .map(|method| LocalSearch::new(method, task));
+    let local_search_interval = config.local_search_interval.max(1);
+    let diversity_mechanism = config.diversity.diversity(ones_probability)?;
+    let mut restarts = config.restart.restarts()?;
+    let mut restart_events = Vec::new();
+
+    let mut schedule = MutationSchedule::new(
+        config.mutation_schedule,
+        config.mutation_rate,
+        config
+            .final_mutation_rate
+            .unwrap_or(config.mutation_rate / 10f64),
+        config.iterations,
+        config.diversity_threshold,
+        task.number_of_objects,
+    );
+    if schedule.schedule() == RateSchedule::SelfAdaptive {
+        population.enable_self_adaptation(Rates {
+            mutation: config.mutation_rate,
+            crossover: config.crossover_rate,
+        });
+    }
+
+    let statistics = (0..config.iterations)
+        .map(|generation| {
+            let hypermutation_rate = restarts
+                .as_mut()
+                .and_then(|restarts| restarts.hypermutation());
+            // measured once, for the schedule and for the statistics of the generation
+            let diversity = population.diversity();
+
+            let parameters = GenerationParameters {
+                tournament,
+                crossover_rate: config.crossover_rate,
+                cutting_point: if config.use_random_cutting_point {
+                    None
+                } else {
+                    Some(cutting_point)
+                },
+                mutation_rate: hypermutation_rate
+                    .unwrap_or_else(|| schedule.rate(generation, Some(diversity))),
+                local_search: local_search
+                    .as_ref()
+                    .filter(|_| generation % local_search_interval == 0),
+                local_search_rate: config.local_search_rate,
+                // the hypermutation overrides the rates carried by the individuals
+                self_adaptive: schedule.schedule() == RateSchedule::SelfAdaptive
+                    && hypermutation_rate.is_none(),
+                integer_mutation: config.integer_mutation,
+                integer_crossover: config.integer_crossover,
+                diversity: diversity_mechanism,
+                population_diversity: Some(diversity),
+            };
+
+            let statistics = population.evolve_generation(task, &parameters, &mut rand)?;
+            schedule.update(statistics.success_ratio);
+
+            let event = restarts.as_mut().and_then(|restarts| {
+                restarts.check(generation, statistics.best, statistics.diversity)
+            });
+            if let (Some(event), Some(restarts)) = (event, &restarts) {
+                match event.policy {
+                    RestartPolicy::Full => population.restart(task, ones_probability, &mut rand),
+                    RestartPolicy::Partial => population.restart_worst(
+                        task,
+                        restarts.fraction(),
+                        ones_probability,
+                        &mut rand,
+                    ),
+                    // the rate is raised by the next generations
+                    RestartPolicy::Hypermutation => {}
+                }
+                restart_events.push(event);
+            }
+
+            Ok(statistics)
+        })
+        .collect::<DynamicResult<Vec<GenerationStatistics>>>()?;
+
+    // the last generation has not been evaluated yet
+    population.update_elite_with(task);
+
+    Ok(Evolution {
+        statistics,
+        best: population
+            .elite()
+            .map(|elite| Solution::from_genome(&elite.genome)),
+        best_fitness: population.elite().map_or(0, |elite| elite.fitness),
+        seed,
+        restarts: restart_events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::Random;
+    use crate::fixtures::{random_interactions, random_task};
+    use rand::SeedableRng;
+    use structopt::StructOpt;
+
+    fn configuration(execution: &[&str]) -> Configuration {
+        let args = [
+            "evolve",
+            "--iterations",
+            "30",
+            "--population-size",
+            "40",
+            "--crossover-rate",
+            "0.9",
+            "--cutting-point",
+            "10",
+            "--mutation-rate",
+            "0.02",
+            "--tournament-size",
+            "3",
+            "--seed",
+            "45",
+            "--output",
+            "results.txt",
+        ];
+        Configuration::from_iter_safe(args.iter().chain(execution.iter())).unwrap()
+    }
+
+    #[test]
+    fn seeded_runs_do_not_depend_on_the_threads() {
+        let rng = &mut Random::seed_from_u64(45);
+        let task = random_task(60, 1..100, 1.0 / 3.0, rng)
+            .with_interactions(random_interactions(60, 100, rng));
+        let run = |execution: &[&str]| {
+            let config = configuration(execution);
+            config
+                .execution
+                .thread_pool()
+                .unwrap()
+                .install(|| evolve(&config, &task).unwrap())
+        };
 
-    Ok(results?)
+        let single = run(&["--threads", "1"]);
+        let best = single.best.as_ref().unwrap();
+        for execution in [&["--threads", "4"][..], &["--sequential"][..]].iter() {
+            let other = run(execution);
+            assert_eq!(other.statistics, single.statistics);
+            assert_eq!(other.best_fitness, single.best_fitness);
+            let other_best = other.best.as_ref().unwrap();
+            assert_eq!(other_best.items, best.items);
+            assert_eq!(other_best.genes, best.genes);
+        }
+    }
 }
@@ -0,0 +1,263 @@
+use crate::data_structures::{bins, task::Task, Float};
+
+/// Linear relaxation of the knapsack, where both constraints are aggregated
+/// into a single one: `lambda_w * weight + lambda_s * size <= lambda_w * W + lambda_s * S`.
+///
+/// Every feasible selection of items satisfies the aggregated constraint,
+/// so the fractional optimum of it is an upper bound for the original task.
+pub struct Relaxation {
+    lambda_weight: f64,
+    lambda_size: f64,
+    // items sorted by their cost per unit of the aggregated resource, the most efficient first
+    order: Vec<usize>,
+}
+
+impl Relaxation {
+    pub fn new(task: &Task, lambda_weight: f64, lambda_size: f64) -> Self {
+        let relaxation = Self {
+            lambda_weight,
+            lambda_size,
+            order: Vec::new(),
+        };
+
+        let efficiency = |i: usize| {
+            let usage = relaxation.usage(task, i);
+            if usage > 0f64 {
+                task.get_costs()[i] as f64 / usage
+            } else {
+                f64::INFINITY
+            }
+        };
+        let mut order: Vec<usize> = (0..task.number_of_objects).collect();
+        order.sort_by(|&a, &b| efficiency(b).partial_cmp(&efficiency(a)).unwrap());
+
+        Self {
+            order,
+            ..relaxation
+        }
+    }
+
+    /// Relaxation of the weight constraint only.
+    pub fn weight(task: &Task) -> Self {
+        Self::new(task, 1f64, 0f64)
+    }
+
+    /// Relaxation of the size constraint only.
+    pub fn size(task: &Task) -> Self {
+        Self::new(task, 0f64, 1f64)
+    }
+
+    /// Surrogate relaxation, where both constraints are normalized by the capacities.
+    pub fn surrogate(task: &Task) -> Self {
+        Self::new(
+            task,
+            1f64 / task.max_weight.max(1) as f64,
+            1f64 / task.max_size.max(1) as f64,
+        )
+    }
+
+    /// Items sorted by their efficiency in the aggregated constraint.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    fn usage(&self, task: &Task, item: usize) -> f64 {
+        self.lambda_weight * task.get_weights()[item] as f64
+            + self.lambda_size * task.get_sizes()[item] as f64
+    }
+
+    /// Returns the fractional optimum over the items accepted by `is_free`,
+    /// when only `remaining_weight` and `remaining_size` are left in the knapsack.
+    ///
+    /// Items, that do not fit into the remaining capacity on their own, are skipped.
+    pub fn bound<F: Fn(usize) -> bool>(
+        &self,
+        task: &Task,
+        remaining_weight: u64,
+        remaining_size: u64,
+        is_free: F,
+    ) -> f64 {
+        let mut capacity =
+            self.lambda_weight * remaining_weight as f64 + self.lambda_size * remaining_size as f64;
+        let mut bound = 0f64;
+
+        for &item in self.order.iter().filter(|&&item| is_free(item)) {
+            if task.get_weights()[item] as u64 > remaining_weight
+                || task.get_sizes()[item] as u64 > remaining_size
+            {
+                continue;
+            }
+
+            let usage = self.usage(task, item);
+            let cost = task.get_costs()[item] as f64;
+            if usage <= capacity {
+                capacity -= usage;
+                bound += cost;
+            } else {
+                bound += cost * capacity / usage;
+                break;
+            }
+        }
+
+        bound
+    }
+}
+
+/// The tightest of the weight, size and surrogate relaxation bounds.
+pub struct RelaxationBound {
+    relaxations: [Relaxation; 3],
+}
+
+impl RelaxationBound {
+    pub fn new(task: &Task) -> Self {
+        Self {
+            relaxations: [
+                Relaxation::surrogate(task),
+                Relaxation::weight(task),
+                Relaxation::size(task),
+            ],
+        }
+    }
+
+    pub fn surrogate(&self) -> &Relaxation {
+        &self.relaxations[0]
+    }
+
+    pub fn bound<F: Fn(usize) -> bool>(
+        &self,
+        task: &Task,
+        remaining_weight: u64,
+        remaining_size: u64,
+        is_free: F,
+    ) -> f64 {
+        self.relaxations
+            .iter()
+            .map(|relaxation| relaxation.bound(task, remaining_weight, remaining_size, &is_free))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Cheap reference values of a task, which allow to judge the results of the algorithm
+/// without running the exact solver.
+#[derive(Debug, Clone)]
+pub struct References {
+    /// value of the greedy solution, that takes the items by their surrogate efficiency,
+    /// or none, if it violates the side constraints
+    pub greedy: Option<u64>,
+    /// upper bound of the linear relaxation of the task
+    pub upper_bound: f64,
+}
+
+impl References {
+    pub fn compute(task: &Task) -> Self {
+        // the binary expansion has the same relaxation, as the task with integer genes
+        if task.get_upper_bounds().is_some() {
+            return Self::compute(&task.binary_expansion().0);
+        }
+
+        let bound = RelaxationBound::new(task);
+        let mut selected = vec![0; task.number_of_objects];
+        match task.get_bins() {
+            // the relaxation of the multiple knapsacks merges the bins into a single one,
+            // but the greedy solution has to fit into each bin
+            Some(bins) => bins::greedy_assignment(task, bins, bound.surrogate().order())
+                .iter()
+                .enumerate()
+                .for_each(|(item, &bin)| selected[item] = (bin != 0) as Float),
+            None => greedy_solution(task, bound.surrogate())
+                .iter()
+                .for_each(|&item| selected[item] = 1),
+        }
+
+        // a mandatory item may not fit, or a group may have no item, which fits
+        let feasible = task
+            .get_constraints()
+            .violations(|item| selected[item] != 0)
+            .total()
+            == 0;
+
+        // the relaxation ignores the pairs of items, so all of their profits are added to it
+        Self {
+            greedy: Some(task.profit(|item| selected[item])).filter(|_| feasible),
+            upper_bound: bound.bound(task, task.max_weight as u64, task.max_size as u64, |_| true)
+                + task.get_interactions().total() as f64,
+        }
+    }
+
+    /// Relative distance of `value` from the upper bound, in percents.
+    pub fn optimality_gap(&self, value: u64) -> f64 {
+        if self.upper_bound > 0f64 {
+            100f64 * (self.upper_bound - value as f64) / self.upper_bound
+        } else {
+            0f64
+        }
+    }
+}
+
+/// Takes the items in the order of the given relaxation, as long as they fit into the knapsack.
+///
+/// The mandatory items are taken first, and an item is skipped, if the side constraints
+/// do not admit it next to the taken items.
+pub fn greedy_solution(task: &Task, relaxation: &Relaxation) -> Vec<usize> {
+    let constraints = task.get_constraints();
+    let mut weight = 0u64;
+    let mut size = 0u64;
+    let mut selected = vec![false; task.number_of_objects];
+
+    let candidates = constraints
+        .mandatory
+        .iter()
+        .chain(relaxation.order().iter())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    candidates
+        .into_iter()
+        .filter(|&item| {
+            let item_weight = task.get_weights()[item] as u64;
+            let item_size = task.get_sizes()[item] as u64;
+            let fits = !selected[item]
+                && weight + item_weight <= task.max_weight as u64
+                && size + item_size <= task.max_size as u64
+                && constraints.admits(item, |other| selected[other]);
+
+            if fits {
+                weight += item_weight;
+                size += item_size;
+                selected[item] = true;
+            }
+
+            fits
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::Random;
+    use crate::exact;
+    use crate::fixtures::{random_interactions, random_task};
+    use rand::SeedableRng;
+    use std::time::Duration;
+
+    const ITEMS: usize = 15;
+
+    #[test]
+    fn upper_bound_and_greedy_enclose_the_optimum() {
+        let mut rng = Random::seed_from_u64(28);
+
+        for round in 0..40 {
+            let mut task = random_task(ITEMS, 1..30, 0.4, &mut rng);
+            if round % 2 == 1 {
+                task = task.with_interactions(random_interactions(ITEMS, 8, &mut rng));
+            }
+
+            let references = References::compute(&task);
+            let optimum = exact::solve_branch_and_bound(&task, Duration::from_secs(60));
+            assert!(optimum.optimal);
+            assert!(references.upper_bound + 1e-6 >= optimum.value as f64);
+            assert!(optimum.value >= references.greedy.unwrap());
+        }
+    }
+}
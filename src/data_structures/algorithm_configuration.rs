@@ -1,3 +1,11 @@
+use super::density::DensityOptions;
+use super::diversity::DiversityOptions;
+use super::execution::ExecutionOptions;
+use super::individual::{IntegerCrossover, IntegerMutation};
+use super::local_search::LocalSearchMethod;
+use super::restart::RestartOptions;
+use super::schedule::RateSchedule;
+use super::selection::TieBreak;
 use std::path::PathBuf;
 
 #[derive(StructOpt, Debug, Clone)]
@@ -37,11 +45,37 @@ pub struct AlgorithmConfiguration {
     #[structopt(short = "t", long)]
     pub tournament_size: usize,
 
-    /// The density of the population - defines, statistically,
-    /// how many ones will be in the initial population
-    /// the higher the value, the lower the density
-    #[structopt(short, long)]
-    pub density: usize,
+    /// if this flag is set, the contestants of a tournament are drawn with replacement,
+    /// so the tournament size can exceed the population size
+    #[structopt(long)]
+    pub tournament_replacement: bool,
+
+    /// how the winner is chosen out of the contestants with the same fitness.
+    /// One of: lowest-index, random
+    #[structopt(long, default_value = "lowest-index")]
+    pub tie_break: TieBreak,
+
+    // The density of the population - defines, statistically,
+    // how many ones will be in the initial population
+    #[structopt(flatten)]
+    pub density: DensityOptions,
+
+    // The number of threads and the seed of the random number generator
+    #[structopt(flatten)]
+    pub execution: ExecutionOptions,
+
+    // The mechanism keeping the population from converging to copies of a single individual
+    #[structopt(flatten)]
+    pub diversity: DiversityOptions,
+
+    // The policy restarting the population, when the search stagnates
+    #[structopt(flatten)]
+    pub restart: RestartOptions,
+
+    /// number of the fitness values remembered by the cache, so the copies of the individuals
+    /// evaluated before are not evaluated again. The cache is disabled, if set to 0
+    #[structopt(long, default_value = "0")]
+    pub fitness_cache: usize,
 
     /// input file with generated objects
     /// by default, it will search for "tasks.csv" file the same one,
@@ -63,4 +97,55 @@ pub struct AlgorithmConfiguration {
     /// for each crossover
     #[structopt(short = "R", long = "random-cutting-point")]
     pub use_random_cutting_point: bool,
+
+    /// local search applied to the offspring, turns the algorithm into a memetic one.
+    /// One of: one-flip, swap, drop-add
+    #[structopt(long)]
+    pub local_search: Option<LocalSearchMethod>,
+
+    /// fraction of the offspring improved with the local search,
+    /// should be set in range [0, 1]
+    #[structopt(long, default_value = "1")]
+    pub local_search_rate: f64,
+
+    /// the local search is applied only every `local-search-interval` generations
+    #[structopt(long, default_value = "1")]
+    pub local_search_interval: usize,
+
+    /// mutation of the genes, if the items can be selected more than once,
+    /// or if they are assigned to one of several bins.
+    /// One of: step, random-reset
+    #[structopt(long, default_value = "step")]
+    pub integer_mutation: IntegerMutation,
+
+    /// crossover of the genes, if the items can be selected more than once,
+    /// or if they are assigned to one of several bins.
+    /// One of: one-point, arithmetic
+    #[structopt(long, default_value = "one-point")]
+    pub integer_crossover: IntegerCrossover,
+
+    /// how the mutation rate changes during the run.
+    /// One of: constant, linear, exponential, diversity, one-fifth, self-adaptive
+    #[structopt(long, default_value = "constant")]
+    pub mutation_schedule: RateSchedule,
+
+    /// mutation rate reached in the last generation by the linear and exponential schedules,
+    /// by default a tenth of the `mutation-rate`
+    #[structopt(long)]
+    pub final_mutation_rate: Option<f64>,
+
+    /// normalized Hamming diversity of the population, below which
+    /// the diversity schedule raises the mutation rate
+    #[structopt(long, default_value = "0.02")]
+    pub diversity_threshold: f64,
+
+    /// csv file to store the statistics of each generation in,
+    /// including the effective mutation and crossover rates
+    #[structopt(long = "statistics", parse(from_os_str))]
+    pub statistics_file: Option<PathBuf>,
+
+    /// file to store the report of the best solution in,
+    /// listing the selected items by their names
+    #[structopt(long = "solution", parse(from_os_str))]
+    pub solution_file: Option<PathBuf>,
 }
@@ -0,0 +1,252 @@
+use super::{individual::IndividualMut, task::Task, Float, FloatDVector, PopulationMatrix, Random};
+use rand::Rng;
+
+/// One of the knapsacks of the multiple knapsack problem.
+///
+/// The genes hold the number of the bin, the item is assigned to, counted from 1,
+/// 0 means, that the item is not assigned to any bin.
+#[derive(Debug, Clone)]
+pub struct Bin {
+    pub name: String,
+    pub max_weight: usize,
+    pub max_size: usize,
+}
+
+/// Weight and size of the items assigned to each bin, by the genes given by `gene`.
+pub fn loads<F: Fn(usize) -> Float>(task: &Task, bins: &[Bin], gene: F) -> Vec<(u64, u64)> {
+    let mut loads = vec![(0u64, 0u64); bins.len()];
+
+    for item in 0..task.number_of_objects {
+        let bin = gene(item) as usize;
+        if bin > 0 {
+            loads[bin - 1].0 += task.get_weights()[item] as u64;
+            loads[bin - 1].1 += task.get_sizes()[item] as u64;
+        }
+    }
+
+    loads
+}
+
+fn fits(bin: &Bin, (weight, size): (u64, u64)) -> bool {
+    weight <= bin.max_weight as u64 && size <= bin.max_size as u64
+}
+
+/// 1 for the individuals, which do not overload any bin, 0 otherwise.
+pub fn get_capacity_vec(population: &PopulationMatrix, task: &Task, bins: &[Bin]) -> FloatDVector {
+    FloatDVector::from_fn(population.ncols(), |individual, _| {
+        let loads = loads(task, bins, |item| population[(item, individual)]);
+        bins.iter().zip(loads).all(|(bin, load)| fits(bin, load)) as u8 as Float
+    })
+}
+
+/// Refills the bins with the assigned items, starting from a random item.
+///
+/// Each item stays in its bin, if it still fits there, otherwise it goes to the first bin,
+/// it fits into, or it is unassigned, if there is no such bin,
+/// so the repaired individual never overloads a bin.
+///
+/// It is meant to follow `Constraints::repair`: the items protected by the side constraints
+/// are placed before all the other ones, so they are unassigned only if the bins
+/// cannot hold them anyway, and the items requiring an unassigned item are unassigned as well.
+pub fn repair(individual: &mut IndividualMut, task: &Task, bins: &[Bin], rng: &mut Random) {
+    let n = individual.len();
+    if n == 0 {
+        return;
+    }
+
+    let constraints = task.get_constraints();
+    let protected = constraints.protected_items(n, |item| individual[item] != 0);
+    let mut loads = vec![(0u64, 0u64); bins.len()];
+    let offset = rng.gen_range(0, n);
+    let order = (0..n).map(|i| (i + offset) % n);
+
+    for item in order
+        .clone()
+        .filter(|&item| protected[item])
+        .chain(order.filter(|&item| !protected[item]))
+    {
+        let bin = individual[item] as usize;
+        if bin == 0 || bin > bins.len() {
+            individual[item] = 0;
+            continue;
+        }
+
+        let weight = task.get_weights()[item] as u64;
+        let size = task.get_sizes()[item] as u64;
+        let fits_into = |other: usize| {
+            fits(
+                &bins[other],
+                (loads[other].0 + weight, loads[other].1 + size),
+            )
+        };
+
+        let target = if fits_into(bin - 1) {
+            Some(bin - 1)
+        } else {
+            (0..bins.len()).find(|&other| fits_into(other))
+        };
+        individual[item] = target.map_or(0, |other| other as Float + 1);
+        if let Some(other) = target {
+            loads[other].0 += weight;
+            loads[other].1 += size;
+        }
+    }
+
+    // unassigning an item only lowers the loads, so the bins stay feasible
+    while let Some(&(item, _)) = constraints
+        .requirements
+        .iter()
+        .find(|&&(item, required)| individual[item] != 0 && individual[required] == 0)
+    {
+        individual[item] = 0;
+    }
+}
+
+/// Assigns the items in the given order, each one to the first bin, it fits into.
+///
+/// The mandatory items are assigned first, and an item is skipped, if the side constraints
+/// do not admit it next to the assigned items.
+pub fn greedy_assignment(task: &Task, bins: &[Bin], order: &[usize]) -> Vec<Float> {
+    let constraints = task.get_constraints();
+    let mut genes = vec![0; task.number_of_objects];
+    let mut loads = vec![(0u64, 0u64); bins.len()];
+
+    for &item in constraints.mandatory.iter().chain(order.iter()) {
+        if genes[item] != 0 || !constraints.admits(item, |other| genes[other] != 0) {
+            continue;
+        }
+
+        let weight = task.get_weights()[item] as u64;
+        let size = task.get_sizes()[item] as u64;
+
+        if let Some(bin) = (0..bins.len())
+            .find(|&bin| fits(&bins[bin], (loads[bin].0 + weight, loads[bin].1 + size)))
+        {
+            loads[bin].0 += weight;
+            loads[bin].1 += size;
+            genes[item] = bin as Float + 1;
+        }
+    }
+
+    genes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{
+        constraints::{Constraints, Group},
+        individual::as_individual_mut,
+    };
+    use rand::{seq::SliceRandom, SeedableRng};
+
+    const ITEMS: usize = 20;
+
+    fn bin(max_weight: usize, max_size: usize) -> Bin {
+        Bin {
+            name: String::new(),
+            max_weight,
+            max_size,
+        }
+    }
+
+    fn task(weights: Vec<Float>, sizes: Vec<Float>, bins: Vec<Bin>) -> Task {
+        let n = weights.len();
+        Task::from_args(
+            n,
+            0,
+            0,
+            FloatDVector::from_vec(weights),
+            FloatDVector::from_vec(sizes),
+            FloatDVector::from_element(n, 1),
+        )
+        .with_bins(bins)
+    }
+
+    #[test]
+    fn capacity_is_checked_per_bin() {
+        let task = task(vec![4, 3, 2], vec![1, 1, 5], vec![bin(5, 5), bin(5, 5)]);
+        // each column is an individual, holding the bins of the items
+        let population = PopulationMatrix::from_column_slice(
+            3,
+            5,
+            &[
+                1, 0, 2, // both bins fit
+                1, 1, 0, // the weight of the first bin is exceeded
+                0, 2, 2, // the size of the second bin is exceeded
+                1, 2, 0, // the total load fits either bin, but it is split
+                0, 0, 0, // nothing is assigned
+            ],
+        );
+
+        let capacity = get_capacity_vec(&population, &task, task.get_bins().unwrap());
+        assert_eq!(capacity.as_slice(), &[1, 0, 0, 1, 1]);
+        assert_eq!(
+            loads(&task, task.get_bins().unwrap(), |item| population
+                [(item, 1)]),
+            vec![(7, 2), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn repaired_child_satisfies_bins_and_constraints() {
+        let mut rng = Random::seed_from_u64(40);
+        let weights = vec![1; ITEMS];
+        let sizes = (0..ITEMS).map(|_| rng.gen_range(1, 3)).collect();
+        // the bins cannot hold all items, but any seven of them, which are protected at most,
+        // fit into the first bin, so none of the protected items has to be dropped
+        let task =
+            task(weights, sizes, vec![bin(9, 14), bin(3, 6)]).with_constraints(Constraints {
+                mandatory: vec![0, 1],
+                forbidden: vec![2],
+                conflicts: vec![(3, 4)],
+                requirements: vec![(5, 6), (7, 8)],
+                groups: vec![Group {
+                    name: "group".to_string(),
+                    items: vec![9, 10, 11],
+                }],
+            });
+        let bins = task.get_bins().unwrap();
+
+        for _ in 0..500 {
+            let mut genes: Vec<Float> = (0..ITEMS).map(|_| rng.gen_range(0, 3)).collect();
+            let mut individual = as_individual_mut(&mut genes);
+            task.get_constraints().repair(&mut individual, &mut rng);
+            repair(&mut individual, &task, bins, &mut rng);
+
+            let loads = loads(&task, bins, |item| genes[item]);
+            assert!(bins.iter().zip(loads).all(|(bin, load)| fits(bin, load)));
+            let violations = task.get_constraints().violations(|item| genes[item] != 0);
+            assert_eq!(violations.total(), 0, "{:?}", genes);
+        }
+    }
+
+    #[test]
+    fn greedy_assignment_satisfies_constraints() {
+        let mut rng = Random::seed_from_u64(40);
+        // the bins hold all the items together, so only the side constraints skip any of them
+        let task = task(vec![1; ITEMS], vec![1; ITEMS], vec![bin(12, 12), bin(8, 8)])
+            .with_constraints(Constraints {
+                mandatory: vec![0, 1],
+                forbidden: vec![2],
+                conflicts: vec![(3, 4)],
+                requirements: vec![(5, 6), (7, 8)],
+                groups: vec![Group {
+                    name: "group".to_string(),
+                    items: vec![9, 10, 11],
+                }],
+            });
+        let bins = task.get_bins().unwrap();
+        let mut order: Vec<usize> = (0..ITEMS).collect();
+
+        for _ in 0..50 {
+            order.shuffle(&mut rng);
+            let genes = greedy_assignment(&task, bins, &order);
+
+            let loads = loads(&task, bins, |item| genes[item]);
+            assert!(bins.iter().zip(loads).all(|(bin, load)| fits(bin, load)));
+            let violations = task.get_constraints().violations(|item| genes[item] != 0);
+            assert_eq!(violations.total(), 0, "{:?}", genes);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use super::Float;
+
+/// Bounded cache of the fitness of the genomes, keyed by their hashes.
+///
+/// Each hash has a single slot, so a new genome replaces the older one in the same slot.
+/// Only the hashes are kept, the genomes with the same hash are considered the same.
+#[derive(Debug, Clone)]
+pub struct FitnessCache {
+    slots: Vec<Option<(u64, Float)>>,
+}
+
+impl FitnessCache {
+    /// Cache remembering at most `size` fitness values.
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    /// Fitness of the genome with the given hash, if it is remembered.
+    pub fn get(&self, hash: u64) -> Option<Float> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        self.slots[self.slot(hash)]
+            .filter(|&(slot_hash, _)| slot_hash == hash)
+            .map(|(_, fitness)| fitness)
+    }
+
+    pub fn insert(&mut self, hash: u64, fitness: Float) {
+        if !self.slots.is_empty() {
+            let slot = self.slot(hash);
+            self.slots[slot] = Some((hash, fitness));
+        }
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        (hash % self.slots.len() as u64) as usize
+    }
+}
+
+// multiplier of the FxHash function, used by the compiler for its own hash maps
+const HASH_MULTIPLIER: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+// number of the independent hashes, which are combined at the end,
+// so the multiplications of the hashes do not wait for each other
+const HASH_LANES: usize = 4;
+
+/// Fast, non cryptographic hash of the genes.
+pub fn genome_hash(genes: &[Float]) -> u64 {
+    let mix = |hash: u64, word: u64| (hash.rotate_left(5) ^ word).wrapping_mul(HASH_MULTIPLIER);
+    let mut lanes = [0u64; HASH_LANES];
+
+    let chunks = genes.chunks_exact(HASH_LANES);
+    let remainder = chunks.remainder();
+    for chunk in chunks {
+        for (lane, &gene) in lanes.iter_mut().zip(chunk.iter()) {
+            *lane = mix(*lane, gene as u64);
+        }
+    }
+    for (lane, &gene) in lanes.iter_mut().zip(remainder.iter()) {
+        *lane = mix(*lane, gene as u64);
+    }
+
+    let hash = lanes
+        .iter()
+        .fold(genes.len() as u64, |hash, &lane| mix(hash, lane));
+    // the finalizer of SplitMix64, so all the bits depend on all the genes
+    let hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembered_fitness_is_hit() {
+        let mut cache = FitnessCache::new(8);
+        assert_eq!(cache.get(3), None);
+
+        cache.insert(3, 42);
+        assert_eq!(cache.get(3), Some(42));
+        // another hash in the same slot misses
+        assert_eq!(cache.get(11), None);
+        assert_eq!(cache.get(4), None);
+
+        cache.insert(3, 7);
+        assert_eq!(cache.get(3), Some(7));
+    }
+
+    #[test]
+    fn newer_hash_evicts_older_one() {
+        let mut cache = FitnessCache::new(8);
+        cache.insert(3, 42);
+        cache.insert(5, 10);
+        cache.insert(11, 13);
+
+        assert_eq!(cache.get(3), None);
+        assert_eq!(cache.get(11), Some(13));
+        assert_eq!(cache.get(5), Some(10));
+    }
+
+    #[test]
+    fn empty_cache_remembers_nothing() {
+        let mut cache = FitnessCache::new(0);
+        cache.insert(3, 42);
+        assert_eq!(cache.get(3), None);
+    }
+
+    #[test]
+    fn hash_depends_on_every_gene_and_length() {
+        let genes: Vec<Float> = (0..37).map(|gene| gene % 3).collect();
+        let hash = genome_hash(&genes);
+        assert_eq!(genome_hash(&genes.clone()), hash);
+
+        for gene in 0..genes.len() {
+            let mut changed = genes.clone();
+            changed[gene] += 1;
+            assert_ne!(genome_hash(&changed), hash);
+        }
+
+        let mut longer = genes.clone();
+        longer.push(0);
+        assert_ne!(genome_hash(&longer), hash);
+        assert_ne!(genome_hash(&[]), genome_hash(&[0]));
+    }
+}
@@ -0,0 +1,455 @@
+use super::{individual::IndividualMut, DynamicResult, Random};
+use rand::Rng;
+use std::fmt;
+
+// repairing one constraint can break another one, so the repair is repeated a few times
+const REPAIR_PASSES: usize = 4;
+
+/// Side constraints on the selection of the items.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    /// items, that have to be selected
+    pub mandatory: Vec<usize>,
+    /// items, that cannot be selected
+    pub forbidden: Vec<usize>,
+    /// pairs of items, that cannot be selected together
+    pub conflicts: Vec<(usize, usize)>,
+    /// pairs `(item, required)`, where the item can be selected only together with the required one
+    pub requirements: Vec<(usize, usize)>,
+    /// groups of items, from which exactly one item has to be selected
+    pub groups: Vec<Group>,
+}
+
+/// Options of a multiple choice, exactly one of them is selected.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub name: String,
+    pub items: Vec<usize>,
+}
+
+impl Group {
+    /// Collects the groups from the group names of the items, in the order of their first item.
+    /// Items without a group name do not belong to any group.
+    pub fn from_item_groups(item_groups: &[Option<String>]) -> Vec<Group> {
+        let mut groups: Vec<Group> = Vec::new();
+
+        for (item, name) in item_groups.iter().enumerate() {
+            if let Some(name) = name {
+                match groups.iter_mut().find(|group| &group.name == name) {
+                    Some(group) => group.items.push(item),
+                    None => groups.push(Group {
+                        name: name.clone(),
+                        items: vec![item],
+                    }),
+                }
+            }
+        }
+
+        groups
+    }
+}
+
+/// Number of the violated constraints of each kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Violations {
+    pub mandatory: usize,
+    pub forbidden: usize,
+    pub conflicts: usize,
+    pub requirements: usize,
+    /// groups without exactly one selected item
+    pub groups: usize,
+}
+
+impl Violations {
+    pub fn total(&self) -> usize {
+        self.mandatory + self.forbidden + self.conflicts + self.requirements + self.groups
+    }
+}
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "mandatory: {}, forbidden: {}, conflicts: {}, requirements: {}, groups: {}",
+            self.mandatory, self.forbidden, self.conflicts, self.requirements, self.groups
+        )
+    }
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.mandatory.is_empty()
+            && self.forbidden.is_empty()
+            && self.conflicts.is_empty()
+            && self.requirements.is_empty()
+            && self.groups.is_empty()
+    }
+
+    /// Adds the constraint of the given kind: `mandatory` or `forbidden` with a single item,
+    /// `conflict` or `requires` with a pair of items.
+    pub fn add(&mut self, kind: &str, items: &[usize]) -> DynamicResult<()> {
+        match (kind, items) {
+            ("mandatory", &[item]) => self.mandatory.push(item),
+            ("forbidden", &[item]) => self.forbidden.push(item),
+            ("conflict", &[a, b]) => self.conflicts.push((a, b)),
+            ("requires", &[item, required]) => self.requirements.push((item, required)),
+            ("mandatory", _) | ("forbidden", _) | ("conflict", _) | ("requires", _) => {
+                return Err(format!(
+                    "wrong number of items in {} constraint: {}",
+                    kind,
+                    items.len()
+                )
+                .into())
+            }
+            _ => {
+                return Err(format!(
+                "unknown constraint: {}, expected one of: mandatory, forbidden, conflict, requires",
+                kind
+            )
+                .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks, that all the items exist, and no item is both mandatory and forbidden.
+    pub fn validate(&self, number_of_objects: usize) -> DynamicResult<()> {
+        let items = self
+            .mandatory
+            .iter()
+            .chain(self.forbidden.iter())
+            .chain(self.conflicts.iter().flat_map(|(a, b)| vec![a, b]))
+            .chain(self.requirements.iter().flat_map(|(a, b)| vec![a, b]))
+            .chain(self.groups.iter().flat_map(|group| group.items.iter()));
+
+        for &item in items {
+            if item >= number_of_objects {
+                return Err(format!("constraint refers to a missing item {}", item).into());
+            }
+        }
+
+        if let Some(item) = self
+            .mandatory
+            .iter()
+            .find(|item| self.forbidden.contains(item))
+        {
+            return Err(format!("item {} is both mandatory and forbidden", item).into());
+        }
+
+        if let Some(group) = self.groups.iter().find(|group| group.items.is_empty()) {
+            return Err(format!("group {} has no items", group.name).into());
+        }
+
+        Ok(())
+    }
+
+    /// Counts the violated constraints for the individual, which selects the items accepted by `is_selected`.
+    pub fn violations<F: Fn(usize) -> bool>(&self, is_selected: F) -> Violations {
+        Violations {
+            mandatory: self.mandatory.iter().filter(|&&i| !is_selected(i)).count(),
+            forbidden: self.forbidden.iter().filter(|&&i| is_selected(i)).count(),
+            conflicts: self
+                .conflicts
+                .iter()
+                .filter(|&&(a, b)| is_selected(a) && is_selected(b))
+                .count(),
+            requirements: self
+                .requirements
+                .iter()
+                .filter(|&&(item, required)| is_selected(item) && !is_selected(required))
+                .count(),
+            groups: self
+                .groups
+                .iter()
+                .filter(|group| group.items.iter().filter(|&&i| is_selected(i)).count() != 1)
+                .count(),
+        }
+    }
+
+    /// The selected item of each group, or none, if the group does not have exactly one selected item.
+    pub fn chosen_options<F: Fn(usize) -> bool>(&self, is_selected: F) -> Vec<Option<usize>> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let mut selected = group.items.iter().cloned().filter(|&i| is_selected(i));
+                match (selected.next(), selected.next()) {
+                    (Some(item), None) => Some(item),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the item can be added to the selection given by `is_selected`:
+    /// it is not forbidden, does not conflict with a selected item,
+    /// its required items are selected, and no other item of its group is selected.
+    pub fn admits<F: Fn(usize) -> bool>(&self, item: usize, is_selected: F) -> bool {
+        !self.forbidden.contains(&item)
+            && self
+                .conflicts
+                .iter()
+                .all(|&(a, b)| !(a == item && is_selected(b) || b == item && is_selected(a)))
+            && self
+                .requirements
+                .iter()
+                .all(|&(dependent, required)| dependent != item || is_selected(required))
+            && self.groups.iter().all(|group| {
+                !group.items.contains(&item)
+                    || group
+                        .items
+                        .iter()
+                        .all(|&other| other == item || !is_selected(other))
+            })
+    }
+
+    /// Items, which cannot be dropped from the selection given by `is_selected`
+    /// without violating a constraint: the mandatory ones, the ones required by a selected item,
+    /// and the selected items of the groups.
+    pub fn protected_items<F: Fn(usize) -> bool>(
+        &self,
+        number_of_objects: usize,
+        is_selected: F,
+    ) -> Vec<bool> {
+        let mut protected = vec![false; number_of_objects];
+
+        self.mandatory
+            .iter()
+            .for_each(|&item| protected[item] = true);
+        self.requirements
+            .iter()
+            .filter(|&&(item, _)| is_selected(item))
+            .for_each(|&(_, required)| protected[required] = true);
+        self.groups
+            .iter()
+            .flat_map(|group| group.items.iter())
+            .filter(|&&item| is_selected(item))
+            .for_each(|&item| protected[item] = true);
+
+        protected
+    }
+
+    /// Changes the individual, so it satisfies the constraints, if it is possible in a few passes.
+    ///
+    /// Mandatory items are added and forbidden ones dropped, an item missing its required item
+    /// gets it added, unless it is forbidden, and of two conflicting items
+    /// the one, that is not mandatory, is dropped.
+    /// Finally a group without a selected item gets a random allowed one,
+    /// and a group with more selected items keeps only one of them, preferably a mandatory one.
+    pub fn repair(&self, individual: &mut IndividualMut, rng: &mut Random) {
+        if self.is_empty() {
+            return;
+        }
+
+        for _ in 0..REPAIR_PASSES {
+            self.mandatory
+                .iter()
+                .for_each(|&item| individual[item] = individual[item].max(1));
+            self.forbidden.iter().for_each(|&item| individual[item] = 0);
+
+            for &(item, required) in self.requirements.iter() {
+                if individual[item] != 0 && individual[required] == 0 {
+                    if self.forbidden.contains(&required) {
+                        individual[item] = 0;
+                    } else {
+                        individual[required] = 1;
+                    }
+                }
+            }
+
+            for &(a, b) in self.conflicts.iter() {
+                if individual[a] != 0 && individual[b] != 0 {
+                    let drop = match (self.mandatory.contains(&a), self.mandatory.contains(&b)) {
+                        (true, false) => b,
+                        (false, true) => a,
+                        _ if rng.gen_bool(0.5) => a,
+                        _ => b,
+                    };
+                    individual[drop] = 0;
+                }
+            }
+
+            for group in self.groups.iter() {
+                self.repair_group(group, individual, rng);
+            }
+
+            if self.violations(|item| individual[item] != 0).total() == 0 {
+                break;
+            }
+        }
+    }
+
+    fn repair_group(&self, group: &Group, individual: &mut IndividualMut, rng: &mut Random) {
+        let selected: Vec<usize> = group
+            .items
+            .iter()
+            .cloned()
+            .filter(|&item| individual[item] != 0)
+            .collect();
+
+        let keep = match selected.len() {
+            1 => return,
+            0 => {
+                let allowed: Vec<usize> = group
+                    .items
+                    .iter()
+                    .cloned()
+                    .filter(|item| !self.forbidden.contains(item))
+                    .collect();
+                let candidates = if allowed.is_empty() {
+                    &group.items
+                } else {
+                    &allowed
+                };
+                candidates[rng.gen_range(0, candidates.len())]
+            }
+            _ => selected
+                .iter()
+                .cloned()
+                .find(|item| self.mandatory.contains(item))
+                .unwrap_or_else(|| selected[rng.gen_range(0, selected.len())]),
+        };
+
+        // the kept item keeps its number of copies
+        let copies = individual[keep].max(1);
+        selected.iter().for_each(|&item| individual[item] = 0);
+        individual[keep] = copies;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{
+        individual::{as_individual, as_individual_mut, Crossover, Mutate},
+        Float,
+    };
+    use rand::SeedableRng;
+
+    const ITEMS: usize = 16;
+
+    fn constraints() -> Constraints {
+        Constraints {
+            mandatory: vec![0, 1],
+            forbidden: vec![2, 3],
+            // the mandatory item wins the conflict
+            conflicts: vec![(4, 5), (0, 6), (7, 8)],
+            // the item requiring a forbidden one is dropped
+            requirements: vec![(9, 10), (11, 2), (6, 12)],
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn violations_are_counted() {
+        let constraints = constraints();
+        let selected = [2, 4, 5, 9, 11];
+
+        let violations = constraints.violations(|item| selected.contains(&item));
+        assert_eq!(
+            violations,
+            Violations {
+                mandatory: 2,
+                forbidden: 1,
+                conflicts: 1,
+                requirements: 1,
+                groups: 0,
+            }
+        );
+        assert_eq!(violations.total(), 5);
+    }
+
+    #[test]
+    fn repair_reaches_feasible_genome() {
+        let constraints = constraints();
+        let mut rng = Random::seed_from_u64(36);
+
+        for _ in 0..500 {
+            let mut genes: Vec<Float> = (0..ITEMS).map(|_| rng.gen_range(0, 2)).collect();
+            constraints.repair(&mut as_individual_mut(&mut genes), &mut rng);
+
+            let violations = constraints.violations(|item| genes[item] != 0);
+            assert_eq!(violations.total(), 0, "{:?}: {}", genes, violations);
+        }
+    }
+
+    #[test]
+    fn repair_keeps_feasible_genome() {
+        let constraints = constraints();
+        let mut rng = Random::seed_from_u64(36);
+        let mut genes: Vec<Float> = vec![1, 1, 0, 0, 1, 0, 0, 0, 1, 1, 1, 0, 1, 0, 1, 0];
+        let feasible = genes.clone();
+
+        constraints.repair(&mut as_individual_mut(&mut genes), &mut rng);
+        assert_eq!(genes, feasible);
+    }
+
+    #[test]
+    fn invalid_constraints_are_rejected() {
+        let mut constraints = constraints();
+        assert!(constraints.validate(ITEMS).is_ok());
+        assert!(constraints.validate(12).is_err());
+
+        assert!(constraints.add("conflict", &[1]).is_err());
+        assert!(constraints.add("excludes", &[1, 2]).is_err());
+        constraints.add("forbidden", &[0]).unwrap();
+        assert!(constraints.validate(ITEMS).is_err());
+    }
+
+    fn grouped_constraints() -> Constraints {
+        let names: Vec<Option<String>> = (0..ITEMS)
+            .map(|item| match item {
+                0..=3 => Some("first".to_string()),
+                4..=7 => Some("second".to_string()),
+                // the mandatory item of the group is chosen
+                8..=10 => Some("third".to_string()),
+                _ => None,
+            })
+            .collect();
+
+        Constraints {
+            mandatory: vec![9],
+            // the forbidden items of the group are never chosen
+            forbidden: vec![4, 5, 6],
+            groups: Group::from_item_groups(&names),
+            ..Constraints::default()
+        }
+    }
+
+    #[test]
+    fn groups_are_collected_from_item_names() {
+        let groups = grouped_constraints().groups;
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].name, "first");
+        assert_eq!(groups[0].items, vec![0, 1, 2, 3]);
+        assert_eq!(groups[1].items, vec![4, 5, 6, 7]);
+        assert_eq!(groups[2].items, vec![8, 9, 10]);
+    }
+
+    #[test]
+    fn one_item_per_group_after_crossover_and_mutation() {
+        let constraints = grouped_constraints();
+        let mut rng = Random::seed_from_u64(37);
+        let parent = |rng: &mut Random| {
+            let mut genes: Vec<Float> = (0..ITEMS).map(|_| rng.gen_range(0, 2)).collect();
+            constraints.repair(&mut as_individual_mut(&mut genes), rng);
+            genes
+        };
+
+        for _ in 0..500 {
+            let (first, second) = (parent(&mut rng), parent(&mut rng));
+            let mut genes: Vec<Float> = vec![0; ITEMS];
+            let mut child = as_individual_mut(&mut genes);
+            child.random_crossover(as_individual(&first), as_individual(&second), 0.9, &mut rng);
+            child.mutate(0.2, &mut rng);
+            constraints.repair(&mut child, &mut rng);
+
+            let chosen = constraints.chosen_options(|item| genes[item] != 0);
+            assert_eq!(chosen[0].map(|item| item < 4), Some(true), "{:?}", genes);
+            assert_eq!(chosen[1], Some(7), "{:?}", genes);
+            assert_eq!(chosen[2], Some(9), "{:?}", genes);
+            assert_eq!(constraints.violations(|item| genes[item] != 0).total(), 0);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use super::{task::Task, DynamicResult, FloatDVector};
+
+// Options describing how many ones are placed in the initial population.
+//
+// At most one of them can be set. If none is given, the probability is computed
+// from the loaded task, so an average initial individual just fits into the knapsack.
+#[derive(StructOpt, Debug, Clone, Default)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DensityOptions {
+    /// probability of each gene being set to one in the initial population,
+    /// should be set in range (0, 1)
+    #[structopt(long, conflicts_with_all = &["expected-items", "density"])]
+    pub ones_probability: Option<f64>,
+
+    /// expected number of selected items in each individual of the initial population
+    #[structopt(long, conflicts_with_all = &["ones-probability", "density"])]
+    pub expected_items: Option<usize>,
+
+    /// The old way of setting the density of the population, kept for compatibility.
+    /// Each gene is set to one with the probability of 1 / density,
+    /// so the higher the value, the lower the density
+    #[structopt(short, long, conflicts_with_all = &["ones-probability", "expected-items"])]
+    pub density: Option<usize>,
+}
+
+impl DensityOptions {
+    /// Returns the probability of a single gene being set to one in the initial population.
+    pub fn ones_probability(&self, task: &Task) -> DynamicResult<f64> {
+        let probability = match (self.ones_probability, self.expected_items, self.density) {
+            (Some(probability), _, _) => probability,
+            (_, Some(expected_items), _) => {
+                if task.number_of_objects == 0 {
+                    return Err("cannot select items from an empty task".into());
+                }
+
+                expected_items as f64 / task.number_of_objects as f64
+            }
+            (_, _, Some(density)) => {
+                if density == 0 {
+                    return Err("density has to be a positive, non zero integer".into());
+                }
+
+                1f64 / density as f64
+            }
+            _ => capacity_aware_probability(task),
+        };
+
+        if probability <= 0f64 || probability >= 1f64 {
+            return Err(format!(
+                "the probability of selecting an item has to be in range (0, 1), got {}",
+                probability
+            )
+            .into());
+        }
+
+        Ok(probability)
+    }
+}
+
+/// Computes the probability, for which the expected weight and size
+/// of a random individual are equal to the tighter of the two capacities.
+///
+/// If the items can be selected more than once, each selected item counts with the mean
+/// of the number of its copies, drawn uniformly by `Population::draw_copies`.
+pub fn capacity_aware_probability(task: &Task) -> f64 {
+    let ratio = |capacity: usize, total: f64| {
+        if total > 0f64 {
+            capacity as f64 / total
+        } else {
+            1f64
+        }
+    };
+
+    let expected_copies = |item: usize| {
+        task.get_upper_bounds().map_or(1f64, |upper_bounds| {
+            (upper_bounds[item] as f64 + 1f64) / 2f64
+        })
+    };
+    let total = |values: &FloatDVector| {
+        values
+            .iter()
+            .enumerate()
+            .map(|(item, &value)| value as f64 * expected_copies(item))
+            .sum()
+    };
+
+    let total_weight = total(task.get_weights());
+    let total_size = total(task.get_sizes());
+
+    let probability = ratio(task.max_weight, total_weight).min(ratio(task.max_size, total_size));
+
+    // keep the probability inside of the open range, so neither an empty,
+    // nor a full population is generated
+    let lowest = 1f64 / (task.number_of_objects.max(1) as f64 + 1f64);
+    probability.clamp(lowest, 1f64 - lowest)
+}
@@ -0,0 +1,200 @@
+use super::{DynamicResult, Float, FloatDVector, PopulationMatrix};
+use rayon::{iter::ParallelIterator, slice::ParallelSlice};
+use std::str::FromStr;
+
+/// How the population is kept from converging to the copies of a single individual.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiversityMethod {
+    /// the copies of the children are replaced with random individuals
+    Deduplication,
+    /// the parents are selected by their fitness divided by the number of their neighbours
+    Sharing,
+    /// each child replaces the closer of its parents, if it is at least as good
+    Crowding,
+    /// each child replaces the closest of several random individuals, if it is at least as good
+    RestrictedTournament,
+}
+
+impl FromStr for DiversityMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deduplication" => Ok(DiversityMethod::Deduplication),
+            "sharing" => Ok(DiversityMethod::Sharing),
+            "crowding" => Ok(DiversityMethod::Crowding),
+            "restricted-tournament" => Ok(DiversityMethod::RestrictedTournament),
+            _ => Err(format!(
+                "unknown diversity method: {}, expected one of: deduplication, sharing, \
+                 crowding, restricted-tournament",
+                s
+            )),
+        }
+    }
+}
+
+// Options of the mechanism maintaining the diversity of the population.
+#[derive(StructOpt, Debug, Clone, Default)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DiversityOptions {
+    /// mechanism keeping the population diverse, none by default.
+    /// One of: deduplication, sharing, crowding, restricted-tournament
+    #[structopt(long)]
+    pub diversity: Option<DiversityMethod>,
+
+    /// normalized Hamming distance, below which the individuals share their fitness,
+    /// should be set in range (0, 1]
+    #[structopt(long, default_value = "0.1")]
+    pub sharing_radius: f64,
+
+    /// number of the random individuals, the closest of which competes with a child
+    /// in the restricted tournament replacement
+    #[structopt(long, default_value = "10")]
+    pub replacement_window: usize,
+}
+
+impl DiversityOptions {
+    /// The diversity mechanism applied by each generation, if any is chosen.
+    /// `ones_probability` is the density of the individuals injected into the population.
+    pub fn diversity(&self, ones_probability: f64) -> DynamicResult<Option<Diversity>> {
+        let method = match self.diversity {
+            Some(method) => method,
+            None => return Ok(None),
+        };
+
+        if self.sharing_radius <= 0f64 || self.sharing_radius > 1f64 {
+            return Err("sharing radius has to be in range (0, 1]".into());
+        }
+        if self.replacement_window == 0 {
+            return Err("replacement window has to be a positive, non zero integer".into());
+        }
+
+        Ok(Some(Diversity {
+            method,
+            sharing_radius: self.sharing_radius,
+            replacement_window: self.replacement_window,
+            ones_probability,
+        }))
+    }
+}
+
+/// Parameters of the diversity mechanism used by a generation.
+#[derive(Debug, Clone, Copy)]
+pub struct Diversity {
+    pub method: DiversityMethod,
+    pub sharing_radius: f64,
+    pub replacement_window: usize,
+    /// probability of each gene of an injected individual being selected
+    pub ones_probability: f64,
+}
+
+/// Number of the genes, which differ between the individuals.
+pub fn hamming_distance(first: &[Float], second: &[Float]) -> usize {
+    first
+        .iter()
+        .zip(second.iter())
+        .filter(|(first, second)| first != second)
+        .count()
+}
+
+/// Fitness of each individual divided by its niche count, the sum of `1 - d / radius`
+/// over the individuals within the normalized Hamming distance `d < radius`, including itself.
+pub fn shared_fitness(
+    population: &PopulationMatrix,
+    evaluation: &FloatDVector,
+    radius: f64,
+    parallel: bool,
+) -> FloatDVector {
+    let cols = population.nrows().max(1);
+    let genes = population.as_slice();
+    let niche_count = |individual: &[Float]| {
+        genes
+            .chunks(cols)
+            .map(|other| hamming_distance(individual, other) as f64 / cols as f64)
+            .filter(|&distance| distance < radius)
+            .map(|distance| 1f64 - distance / radius)
+            .sum::<f64>()
+    };
+
+    let niche_counts: Vec<f64> = if parallel {
+        genes.par_chunks(cols).map(niche_count).collect()
+    } else {
+        genes.chunks(cols).map(niche_count).collect()
+    };
+
+    // the niche count is at least one, as each individual is its own neighbour
+    FloatDVector::from_iterator(
+        evaluation.len(),
+        evaluation
+            .iter()
+            .zip(niche_counts.iter())
+            .map(|(&fitness, &count)| (fitness as f64 / count.max(1f64)) as Float),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(method: &str) -> DiversityOptions {
+        DiversityOptions {
+            diversity: Some(method.parse().unwrap()),
+            sharing_radius: 0.1,
+            replacement_window: 10,
+        }
+    }
+
+    #[test]
+    fn options_are_validated() {
+        assert!(DiversityOptions::default()
+            .diversity(0.5)
+            .unwrap()
+            .is_none());
+        assert!("niching".parse::<DiversityMethod>().is_err());
+
+        let diversity = options("restricted-tournament")
+            .diversity(0.5)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diversity.method, DiversityMethod::RestrictedTournament);
+        assert_eq!(diversity.ones_probability, 0.5);
+
+        for &radius in &[0f64, 1.5] {
+            let mut options = options("sharing");
+            options.sharing_radius = radius;
+            assert!(options.diversity(0.5).is_err());
+        }
+        let mut options = options("crowding");
+        options.replacement_window = 0;
+        assert!(options.diversity(0.5).is_err());
+    }
+
+    #[test]
+    fn distance_counts_different_genes() {
+        assert_eq!(hamming_distance(&[0, 1, 1, 0], &[0, 1, 1, 0]), 0);
+        assert_eq!(hamming_distance(&[0, 1, 1, 0], &[1, 1, 0, 0]), 2);
+        assert_eq!(hamming_distance(&[2, 0], &[1, 0]), 1);
+    }
+
+    #[test]
+    fn neighbours_share_fitness() {
+        // each column is an individual of ten genes
+        let mut genes = vec![0; 40];
+        // the second individual differs from the first one in a single gene,
+        // the third one is a copy of the first one, the last one is far from all of them
+        genes[10] = 1;
+        genes[30..40].iter_mut().for_each(|gene| *gene = 1);
+        let population = PopulationMatrix::from_column_slice(10, 4, &genes);
+        let evaluation = FloatDVector::from_vec(vec![120, 120, 120, 120]);
+
+        for &parallel in &[false, true] {
+            let shared = shared_fitness(&population, &evaluation, 0.2, parallel);
+            // the niche counts are 1 + 0.5 + 1 for the copies, 0.5 + 1 + 0.5 for the other one
+            assert_eq!(shared.as_slice(), &[48, 60, 48, 120]);
+        }
+
+        // within the smaller radius only the copies share their fitness
+        let shared = shared_fitness(&population, &evaluation, 0.05, false);
+        assert_eq!(shared.as_slice(), &[60, 120, 60, 120]);
+    }
+}
@@ -0,0 +1,211 @@
+use super::{task::Task, Float, PopulationMatrix};
+use std::str::FromStr;
+
+/// How the weights, sizes and costs of the individuals are summed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EvaluationBackend {
+    /// sparse, if the measured density of the population is below `sparse_density`,
+    /// packed for the binary genes and dense for the integer ones otherwise
+    #[default]
+    Auto,
+    /// multiplies every gene by the values of its item
+    Dense,
+    /// lists the selected items of each individual and sums only their values
+    Sparse,
+    /// packs the genes into bits and sums the values of the items masked by them,
+    /// for several individuals at once. The integer genes are summed by the dense back-end
+    Packed,
+}
+
+impl FromStr for EvaluationBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(EvaluationBackend::Auto),
+            "dense" => Ok(EvaluationBackend::Dense),
+            "sparse" => Ok(EvaluationBackend::Sparse),
+            "packed" => Ok(EvaluationBackend::Packed),
+            _ => Err(format!(
+                "unknown evaluation back-end: {}, expected one of: auto, dense, sparse, packed",
+                s
+            )),
+        }
+    }
+}
+
+// below this fraction of non zero genes the sparse back-end is faster than the dense
+// and packed ones, if the items do not interact
+const SPARSE_DENSITY: f64 = 0.01;
+
+// number of the genes checked by `measured_density`
+const DENSITY_SAMPLE: usize = 4096;
+
+const GOLDEN_RATIO: f64 = 1.618_033_988_749_895;
+
+// number of the genes checked at once by `selected_items`
+const SKIPPED_CHUNK: usize = 16;
+
+impl EvaluationBackend {
+    /// The back-end evaluating the population, one of: dense, sparse, packed.
+    pub fn resolve(self, population: &PopulationMatrix, task: &Task) -> Self {
+        match self {
+            EvaluationBackend::Auto if measured_density(population) < sparse_density(task) => {
+                EvaluationBackend::Sparse
+            }
+            // the bits cannot count the copies of the items
+            EvaluationBackend::Auto | EvaluationBackend::Packed => {
+                if task.get_gene_bounds().is_none() {
+                    EvaluationBackend::Packed
+                } else {
+                    EvaluationBackend::Dense
+                }
+            }
+            backend => backend,
+        }
+    }
+}
+
+/// Density of the population, below which the sparse back-end is faster than the other ones.
+///
+/// The other back-ends sum the profits of all the pairs of items, while the sparse one sums
+/// only the pairs of the selected items, so the more pairs, the denser the sparse populations.
+pub fn sparse_density(task: &Task) -> f64 {
+    let pairs = task.get_interactions().pairs().len() as f64;
+    SPARSE_DENSITY * (1f64 + pairs / task.number_of_objects.max(1) as f64)
+}
+
+/// Fraction of the non zero genes of the population,
+/// measured on at most `DENSITY_SAMPLE` genes spread over all the individuals.
+pub fn measured_density(population: &PopulationMatrix) -> f64 {
+    let genes = population.as_slice();
+    if genes.len() <= DENSITY_SAMPLE {
+        let selected = genes.iter().filter(|&&gene| gene != 0).count();
+        return selected as f64 / genes.len().max(1) as f64;
+    }
+
+    // the positions follow the fractions of the multiples of the golden ratio, so unlike
+    // a constant step, they are not aligned with the genes of the individuals
+    let selected = (0..DENSITY_SAMPLE)
+        .map(|i| ((i as f64 * GOLDEN_RATIO).fract() * genes.len() as f64) as usize)
+        .filter(|&position| genes[position] != 0)
+        .count();
+
+    selected as f64 / DENSITY_SAMPLE as f64
+}
+
+/// Replaces the content of `selected` with the indices of the non zero genes.
+pub fn selected_items(genes: &[Float], selected: &mut Vec<usize>) {
+    selected.clear();
+
+    let chunks = genes.chunks_exact(SKIPPED_CHUNK);
+    let remainder = chunks.remainder();
+    for (chunk_index, chunk) in chunks.enumerate() {
+        // the length of the chunk is known, so the chunks of zeros are found with vector instructions
+        if chunk.iter().fold(0, |any, &gene| any | gene) != 0 {
+            push_selected(chunk, chunk_index * SKIPPED_CHUNK, selected);
+        }
+    }
+    push_selected(remainder, genes.len() - remainder.len(), selected);
+}
+
+// pushes the indices of the non zero genes of the chunk, which starts at `offset`
+fn push_selected(chunk: &[Float], offset: usize, selected: &mut Vec<usize>) {
+    selected.extend(
+        chunk
+            .iter()
+            .enumerate()
+            .filter(|(_, &gene)| gene != 0)
+            .map(|(item, _)| offset + item),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{population::Population, Random};
+    use crate::fixtures::{random_interactions, random_task};
+    use rand::{Rng, SeedableRng};
+
+    const ITEMS: usize = 301;
+
+    // genes up to `bound`, non zero with the given probability
+    fn random_population(density: f64, bound: Float, rng: &mut Random) -> PopulationMatrix {
+        PopulationMatrix::from_fn(ITEMS, 40, |_, _| {
+            if rng.gen_bool(density) {
+                rng.gen_range(1, bound + 1)
+            } else {
+                0
+            }
+        })
+    }
+
+    #[test]
+    fn selected_items_are_found_across_chunks() {
+        let mut rng = Random::seed_from_u64(46);
+        let mut selected = vec![7];
+
+        for &len in &[
+            0,
+            1,
+            SKIPPED_CHUNK - 1,
+            SKIPPED_CHUNK,
+            5 * SKIPPED_CHUNK + 3,
+        ] {
+            let genes: Vec<Float> = (0..len).map(|_| rng.gen_range(0, 3)).collect();
+            selected_items(&genes, &mut selected);
+
+            let expected: Vec<usize> = (0..len).filter(|&item| genes[item] != 0).collect();
+            assert_eq!(selected, expected);
+        }
+    }
+
+    #[test]
+    fn sparse_sums_equal_dense_sums() {
+        let mut rng = Random::seed_from_u64(46);
+        let task = random_task(ITEMS, 1..1000, 1.0 / 30.0, &mut rng);
+        let interacting = task
+            .clone()
+            .with_interactions(random_interactions(ITEMS, 600, &mut rng));
+        let bounded = interacting.clone().with_upper_bounds(vec![Some(4); ITEMS]);
+
+        for (task, bound) in [(&task, 1), (&interacting, 1), (&bounded, 4)].iter() {
+            for &density in &[0f64, 0.005, 0.05, 0.5] {
+                let mut population = Population::from(random_population(density, *bound, &mut rng));
+                for &parallel in &[false, true] {
+                    population.set_parallel(parallel);
+                    population.set_evaluation_backend(EvaluationBackend::Dense);
+                    let dense = (population.evaluate(task), population.resource_sums(task));
+
+                    population.set_evaluation_backend(EvaluationBackend::Sparse);
+                    let sparse = (population.evaluate(task), population.resource_sums(task));
+                    assert_eq!(sparse, dense);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn auto_back_end_follows_density() {
+        let mut rng = Random::seed_from_u64(46);
+        let task = random_task(ITEMS, 1..1000, 1.0 / 30.0, &mut rng);
+        let bounded = task.clone().with_upper_bounds(vec![Some(3); ITEMS]);
+        let sparse = random_population(0.001, 1, &mut rng);
+        let dense = random_population(0.3, 1, &mut rng);
+
+        assert!(measured_density(&sparse) < sparse_density(&task));
+        assert!(measured_density(&dense) > sparse_density(&task));
+        assert_eq!(
+            EvaluationBackend::Auto.resolve(&sparse, &task),
+            EvaluationBackend::Sparse
+        );
+        assert_eq!(
+            EvaluationBackend::Auto.resolve(&dense, &task),
+            EvaluationBackend::Packed
+        );
+        assert_eq!(
+            EvaluationBackend::Auto.resolve(&dense, &bounded),
+            EvaluationBackend::Dense
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use super::{evaluation::EvaluationBackend, DynamicResult, Random};
+use rand::{Rng, SeedableRng};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+// Options describing, how the algorithm uses the threads and the random number generator.
+//
+// The individuals draw their random numbers from generators seeded by the index of the individual,
+// so a seeded run gives the same result, regardless of the number of threads.
+#[derive(StructOpt, Debug, Clone, Default)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ExecutionOptions {
+    /// number of threads used by the algorithm, one for each core by default
+    #[structopt(long)]
+    pub threads: Option<usize>,
+
+    /// evaluate and breed the individuals one by one, instead of spreading them across the threads,
+    /// which is faster for small populations and tasks
+    #[structopt(long)]
+    pub sequential: bool,
+
+    /// how the selected items of the individuals are summed, one of: auto, dense, sparse, packed.
+    /// The auto back-end sums only the selected items, if few of them are selected,
+    /// and packs the binary genes into bits otherwise
+    #[structopt(long, default_value = "auto")]
+    pub evaluation: EvaluationBackend,
+
+    /// seed of the random number generator, the same seed and arguments always give the same run
+    #[structopt(long)]
+    pub seed: Option<u64>,
+}
+
+impl ExecutionOptions {
+    /// Pool of the threads, which runs the algorithm.
+    pub fn thread_pool(&self) -> DynamicResult<ThreadPool> {
+        if self.threads == Some(0) {
+            return Err("number of threads has to be positive".into());
+        }
+
+        Ok(ThreadPoolBuilder::new()
+            .num_threads(self.threads.unwrap_or(0))
+            .build()?)
+    }
+
+    /// The given seed, or a random one, if it is not set.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::thread_rng().gen())
+    }
+}
+
+/// Generator of the `index`-th individual, or of the `index`-th trial, derived from `seed`.
+pub fn derived_rng(seed: u64, index: usize) -> Random {
+    Random::seed_from_u64(seed.wrapping_add(index as u64))
+}
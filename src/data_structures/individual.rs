@@ -1,7 +1,8 @@
-use super::{utils::get_sparse_vec, Float};
+use super::{utils::get_sparse_vec, Float, Random};
 use nalgebra::base::dimension::{Dynamic, U1};
 use nalgebra::{SliceStorage, SliceStorageMut};
-use rand::{prelude::ThreadRng, Rng};
+use rand::Rng;
+use std::str::FromStr;
 
 pub type Individual<'a> =
     nalgebra::Matrix<Float, U1, Dynamic, SliceStorage<'a, Float, U1, Dynamic, U1, Dynamic>>;
@@ -9,10 +10,90 @@ pub type Individual<'a> =
 pub type IndividualMut<'a> =
     nalgebra::Matrix<Float, U1, Dynamic, SliceStorageMut<'a, Float, U1, Dynamic, U1, Dynamic>>;
 
+/// Views the contiguous genes of an individual.
+pub fn as_individual(genes: &[Float]) -> Individual<'_> {
+    Individual::from_slice_with_strides_generic(
+        genes,
+        U1,
+        Dynamic::new(genes.len()),
+        U1,
+        Dynamic::new(1),
+    )
+}
+
+/// Views the contiguous genes of an individual, so the operators can change them.
+pub fn as_individual_mut(genes: &mut [Float]) -> IndividualMut<'_> {
+    let len = genes.len();
+    IndividualMut::from_slice_with_strides_generic(
+        genes,
+        U1,
+        Dynamic::new(len),
+        U1,
+        Dynamic::new(1),
+    )
+}
+
+/// Mutation of the integer genes, which count the copies of the items.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegerMutation {
+    /// adds or removes a single copy of the item
+    Step,
+    /// draws the number of copies uniformly, up to the bound of the item
+    RandomReset,
+}
+
+impl FromStr for IntegerMutation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(IntegerMutation::Step),
+            "random-reset" => Ok(IntegerMutation::RandomReset),
+            _ => Err(format!(
+                "unknown integer mutation: {}, expected one of: step, random-reset",
+                s
+            )),
+        }
+    }
+}
+
+/// Crossover of the integer genes, which count the copies of the items.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegerCrossover {
+    /// the same crossover, as for the binary genes
+    OnePoint,
+    /// each gene is the rounded, randomly weighted mean of the genes of the parents
+    Arithmetic,
+}
+
+impl FromStr for IntegerCrossover {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one-point" => Ok(IntegerCrossover::OnePoint),
+            "arithmetic" => Ok(IntegerCrossover::Arithmetic),
+            _ => Err(format!(
+                "unknown integer crossover: {}, expected one of: one-point, arithmetic",
+                s
+            )),
+        }
+    }
+}
+
 pub trait Mutate {
     type Output;
 
-    fn mutate(&mut self, mutation_rate: f64, rng: &mut ThreadRng);
+    fn mutate(&mut self, mutation_rate: f64, rng: &mut Random);
+
+    /// Mutates the integer genes, keeping each of them in range from 0 to its upper bound.
+    fn mutate_integer(
+        &mut self,
+        mutation_rate: f64,
+        upper_bounds: &[Float],
+        method: IntegerMutation,
+        rng: &mut Random,
+    );
 }
 
 pub trait Crossover {
@@ -24,7 +105,7 @@ pub trait Crossover {
         second_parent: Self::Parent,
         crossover_rate: f64,
         cutting_point: usize,
-        rng: &mut ThreadRng,
+        rng: &mut Random,
     );
 
     fn random_crossover(
@@ -32,14 +113,22 @@ pub trait Crossover {
         first_parent: Self::Parent,
         second_parent: Self::Parent,
         crossover_rate: f64,
-        rng: &mut ThreadRng,
+        rng: &mut Random,
+    );
+
+    fn arithmetic_crossover(
+        &mut self,
+        first_parent: Self::Parent,
+        second_parent: Self::Parent,
+        crossover_rate: f64,
+        rng: &mut Random,
     );
 }
 
 impl<'a> Mutate for IndividualMut<'a> {
     type Output = ();
 
-    fn mutate(&mut self, mutation_rate: f64, rng: &mut ThreadRng) {
+    fn mutate(&mut self, mutation_rate: f64, rng: &mut Random) {
         let sparse_vector = get_sparse_vec(
             self.iter().len(),
             (mutation_rate * self.iter().len() as f64) as usize,
@@ -50,6 +139,34 @@ impl<'a> Mutate for IndividualMut<'a> {
             .zip(sparse_vector.iter())
             .for_each(|(gene, to_change)| *gene = (*gene + to_change) % 2)
     }
+
+    fn mutate_integer(
+        &mut self,
+        mutation_rate: f64,
+        upper_bounds: &[Float],
+        method: IntegerMutation,
+        rng: &mut Random,
+    ) {
+        let sparse_vector = get_sparse_vec(
+            self.iter().len(),
+            (mutation_rate * self.iter().len() as f64) as usize,
+            rng,
+        );
+
+        self.iter_mut()
+            .zip(sparse_vector.iter())
+            .zip(upper_bounds.iter())
+            .filter(|((_, &to_change), _)| to_change != 0)
+            .for_each(|((gene, _), &bound)| {
+                *gene = match method {
+                    IntegerMutation::Step if *gene == 0 => bound.min(1),
+                    IntegerMutation::Step if *gene >= bound => bound.saturating_sub(1),
+                    IntegerMutation::Step if rng.gen_bool(0.5) => *gene + 1,
+                    IntegerMutation::Step => *gene - 1,
+                    IntegerMutation::RandomReset => rng.gen_range(0, bound + 1),
+                }
+            })
+    }
 }
 
 impl<'a> Crossover for IndividualMut<'a> {
@@ -63,7 +180,7 @@ impl<'a> Crossover for IndividualMut<'a> {
         second_parent: Self::Parent,
         crossover_rate: f64,
         cutting_point: usize,
-        rng: &mut ThreadRng,
+        rng: &mut Random,
     ) {
         let first_parent_iter = first_parent.iter().cloned();
 
@@ -89,7 +206,7 @@ impl<'a> Crossover for IndividualMut<'a> {
         first_parent: Self::Parent,
         second_parent: Self::Parent,
         crossover_rate: f64,
-        rng: &mut ThreadRng,
+        rng: &mut Random,
     ) {
         let first_parent_iter = first_parent.iter().cloned();
 
@@ -110,4 +227,116 @@ impl<'a> Crossover for IndividualMut<'a> {
 
         self.iter_mut().zip(parents_iter).for_each(|(c, p)| *c = p);
     }
+
+    fn arithmetic_crossover(
+        &mut self,
+        first_parent: Self::Parent,
+        second_parent: Self::Parent,
+        crossover_rate: f64,
+        rng: &mut Random,
+    ) {
+        let first_parent_iter = first_parent.iter().cloned();
+
+        if rng.gen_range(0f64, 1f64) > crossover_rate {
+            self.iter_mut()
+                .zip(first_parent_iter)
+                .for_each(|(c, p)| *c = p);
+
+            return;
+        }
+
+        // the weighted mean of two genes stays within the bound of the item
+        let weight = rng.gen_range(0f64, 1f64);
+        self.iter_mut()
+            .zip(first_parent_iter.zip(second_parent.iter().cloned()))
+            .for_each(|(c, (first, second))| {
+                *c = (weight * first as f64 + (1f64 - weight) * second as f64).round() as Float
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const GENES: usize = 40;
+
+    fn random_genes(upper_bounds: &[Float], rng: &mut Random) -> Vec<Float> {
+        upper_bounds
+            .iter()
+            .map(|&bound| rng.gen_range(0, bound + 1))
+            .collect()
+    }
+
+    fn assert_within_bounds(genes: &[Float], upper_bounds: &[Float]) {
+        genes
+            .iter()
+            .zip(upper_bounds.iter())
+            .for_each(|(gene, bound)| assert!(gene <= bound, "{:?}", genes));
+    }
+
+    #[test]
+    fn integer_mutation_stays_within_bounds() {
+        let mut rng = Random::seed_from_u64(38);
+        // zero bounds, such as of the items, which do not fit, are kept at zero
+        let upper_bounds: Vec<Float> = (0..GENES).map(|_| rng.gen_range(0, 5)).collect();
+
+        for &method in &[IntegerMutation::Step, IntegerMutation::RandomReset] {
+            for _ in 0..200 {
+                let mut genes = random_genes(&upper_bounds, &mut rng);
+                as_individual_mut(&mut genes).mutate_integer(0.5, &upper_bounds, method, &mut rng);
+                assert_within_bounds(&genes, &upper_bounds);
+            }
+        }
+    }
+
+    #[test]
+    fn step_mutation_changes_a_gene_by_one() {
+        let mut rng = Random::seed_from_u64(38);
+        let upper_bounds = vec![3; GENES];
+
+        for _ in 0..200 {
+            let parent = random_genes(&upper_bounds, &mut rng);
+            let mut genes = parent.clone();
+            as_individual_mut(&mut genes).mutate_integer(
+                0.1,
+                &upper_bounds,
+                IntegerMutation::Step,
+                &mut rng,
+            );
+
+            let changes: Vec<i64> = genes
+                .iter()
+                .zip(parent.iter())
+                .map(|(&gene, &parent)| gene as i64 - parent as i64)
+                .filter(|&change| change != 0)
+                .collect();
+            assert_eq!(changes.len(), 4);
+            assert!(changes.iter().all(|change| change.abs() == 1));
+        }
+    }
+
+    #[test]
+    fn arithmetic_crossover_stays_within_bounds() {
+        let mut rng = Random::seed_from_u64(38);
+        let upper_bounds: Vec<Float> = (0..GENES).map(|_| rng.gen_range(0, 10)).collect();
+
+        for _ in 0..200 {
+            let first = random_genes(&upper_bounds, &mut rng);
+            let second = random_genes(&upper_bounds, &mut rng);
+            let mut genes = vec![0; GENES];
+            as_individual_mut(&mut genes).arithmetic_crossover(
+                as_individual(&first),
+                as_individual(&second),
+                1f64,
+                &mut rng,
+            );
+
+            assert_within_bounds(&genes, &upper_bounds);
+            for ((&gene, &first), &second) in genes.iter().zip(first.iter()).zip(second.iter()) {
+                assert!(first.min(second) <= gene && gene <= first.max(second));
+            }
+        }
+    }
 }
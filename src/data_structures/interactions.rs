@@ -0,0 +1,226 @@
+use super::{utils::saturate, DynamicResult, Float, FloatDVector, PopulationMatrix};
+
+/// Sparse, symmetric profits of the pairs of items, earned in addition to their costs,
+/// when both items are selected.
+#[derive(Debug, Clone, Default)]
+pub struct Interactions {
+    // pairs `(i, j, profit)` with `i < j`, each pair is listed once
+    pairs: Vec<(usize, usize, Float)>,
+    // pairs of each item, as `(other item, profit)`
+    neighbours: Vec<Vec<(usize, Float)>>,
+}
+
+impl Interactions {
+    /// Builds the interactions of `number_of_objects` items, profits of repeated pairs are summed.
+    pub fn new(number_of_objects: usize, pairs: &[(usize, usize, Float)]) -> DynamicResult<Self> {
+        let mut merged: Vec<(usize, usize, Float)> = Vec::with_capacity(pairs.len());
+
+        for &(a, b, profit) in pairs.iter() {
+            if a >= number_of_objects || b >= number_of_objects {
+                return Err(format!("interaction refers to a missing item {}", a.max(b)).into());
+            }
+            if a == b {
+                return Err(format!("item {} cannot interact with itself", a).into());
+            }
+            merged.push((a.min(b), a.max(b), profit));
+        }
+
+        merged.sort_unstable_by_key(|&(a, b, _)| (a, b));
+        merged.dedup_by(|next, previous| {
+            let same = (next.0, next.1) == (previous.0, previous.1);
+            if same {
+                previous.2 += next.2;
+            }
+            same
+        });
+
+        let mut neighbours = vec![Vec::new(); number_of_objects];
+        for &(a, b, profit) in merged.iter() {
+            neighbours[a].push((b, profit));
+            neighbours[b].push((a, profit));
+        }
+
+        Ok(Self {
+            pairs: merged,
+            neighbours,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn pairs(&self) -> &[(usize, usize, Float)] {
+        &self.pairs
+    }
+
+    /// Sum of the profits of all the pairs.
+    pub fn total(&self) -> u64 {
+        self.pairs.iter().map(|&(_, _, profit)| profit as u64).sum()
+    }
+
+    /// Profit of the pairs of the individual, which selects `copies(i)` copies of each item.
+    pub fn profit<F: Fn(usize) -> Float>(&self, copies: F) -> u64 {
+        self.pairs
+            .iter()
+            .map(|&(a, b, profit)| profit as u64 * copies(a) as u64 * copies(b) as u64)
+            .sum()
+    }
+
+    /// Change of the profit of the pairs, when a single copy of the item is added to the individual,
+    /// or removed from it, with the copies of the other items given by `copies`.
+    ///
+    /// Used to evaluate the moves of the local search and the mutated children
+    /// without evaluating the whole individual.
+    pub fn delta<F: Fn(usize) -> Float>(&self, item: usize, copies: F) -> u64 {
+        self.neighbours.get(item).map_or(0, |neighbours| {
+            neighbours
+                .iter()
+                .map(|&(other, profit)| profit as u64 * copies(other) as u64)
+                .sum()
+        })
+    }
+
+    /// Profit of the pairs of `child`, derived from the known `parent_profit` of `parent`.
+    ///
+    /// The genes, which differ, are changed one after another, adding the `delta` of each,
+    /// so only the pairs of the changed items are summed. If the changed items have more pairs
+    /// than the whole task, or the profit of the parent is saturated,
+    /// the profit is summed over all the pairs instead.
+    pub fn child_profit(&self, parent: &[Float], parent_profit: Float, child: &[Float]) -> Float {
+        let changed: Vec<usize> = parent
+            .iter()
+            .zip(child.iter())
+            .enumerate()
+            .filter(|(_, (parent, child))| parent != child)
+            .map(|(item, _)| item)
+            .collect();
+        let changed_pairs: usize = changed
+            .iter()
+            .map(|&item| self.neighbours[item].len())
+            .sum();
+        if changed_pairs >= self.pairs.len() || parent_profit == Float::MAX {
+            return self.dense_profit(child);
+        }
+
+        // the items before the changed one already hold the genes of the child
+        let profit = changed.iter().fold(parent_profit as i64, |profit, &item| {
+            let delta = self.delta(item, |other| {
+                if other < item {
+                    child[other]
+                } else {
+                    parent[other]
+                }
+            }) as i64;
+            profit + (child[item] as i64 - parent[item] as i64) * delta
+        });
+
+        saturate(profit.max(0) as u64)
+    }
+
+    /// Profit of the pairs of each individual of the population.
+    pub fn evaluate(&self, population: &PopulationMatrix) -> FloatDVector {
+        FloatDVector::from_iterator(
+            population.ncols(),
+            population
+                .column_iter()
+                .map(|genes| self.dense_profit(genes.as_slice())),
+        )
+    }
+
+    /// Profit of the pairs of an individual, summed over all the pairs.
+    ///
+    /// The profit is summed in `u64`, and saturates at the largest value of a gene.
+    pub fn dense_profit(&self, genes: &[Float]) -> Float {
+        saturate(
+            self.pairs
+                .iter()
+                .map(|&(a, b, profit)| profit as u64 * genes[a] as u64 * genes[b] as u64)
+                .sum(),
+        )
+    }
+
+    /// Profit of the pairs of an individual, summed over the pairs of its `selected` items only.
+    ///
+    /// Saturates like `dense_profit`.
+    pub fn sparse_profit(&self, genes: &[Float], selected: &[usize]) -> Float {
+        saturate(
+            selected
+                .iter()
+                .map(|&a| {
+                    self.neighbours[a]
+                        .iter()
+                        .filter(|&&(b, _)| b > a)
+                        .map(|&(b, profit)| profit as u64 * genes[a] as u64 * genes[b] as u64)
+                        .sum::<u64>()
+                })
+                .sum(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::Random;
+    use crate::fixtures::random_interactions;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn child_profit_equals_dense_profit() {
+        let mut rng = Random::seed_from_u64(39);
+        let items = 60;
+        let interactions = random_interactions(items, 150, &mut rng);
+
+        for trial in 0..200 {
+            // the integer genes select several copies of the items
+            let parent: Vec<Float> = (0..items).map(|_| rng.gen_range(0, 4)).collect();
+            let mut child = parent.clone();
+            // few changes are derived incrementally, many ones are summed over all the pairs
+            let changes = if trial % 2 == 0 {
+                rng.gen_range(0, 8)
+            } else {
+                rng.gen_range(0, items)
+            };
+            for _ in 0..changes {
+                child[rng.gen_range(0, items)] = rng.gen_range(0, 4);
+            }
+
+            let parent_profit = interactions.dense_profit(&parent);
+            assert_eq!(
+                interactions.child_profit(&parent, parent_profit, &child),
+                interactions.dense_profit(&child)
+            );
+        }
+    }
+
+    #[test]
+    fn profits_saturate_at_the_largest_value() {
+        let half = Float::MAX / 2;
+        let interactions = Interactions::new(4, &[(0, 1, half), (1, 2, half)]).unwrap();
+        let selected = |genes: &[Float]| -> Vec<usize> {
+            (0..genes.len()).filter(|&item| genes[item] != 0).collect()
+        };
+
+        // the sum just fits, or it is saturated instead of wrapped around
+        let fitting: Vec<Float> = vec![1, 1, 1, 0];
+        let exceeding: Vec<Float> = vec![1, 2, 1, 0];
+        for (genes, expected) in [(&fitting, Float::MAX - 1), (&exceeding, Float::MAX)].iter() {
+            assert_eq!(interactions.dense_profit(genes), *expected);
+            assert_eq!(
+                interactions.sparse_profit(genes, &selected(genes)),
+                *expected
+            );
+        }
+
+        // the profits derived from a saturated parent are summed again
+        assert_eq!(
+            interactions.child_profit(&fitting, Float::MAX - 1, &exceeding),
+            Float::MAX
+        );
+        assert_eq!(
+            interactions.child_profit(&exceeding, Float::MAX, &fitting),
+            Float::MAX - 1
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use super::{individual::IndividualMut, task::Task, Float, Random};
+use crate::bounds::Relaxation;
+use rand::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocalSearchMethod {
+    /// flips single genes, as long as it improves the individual
+    OneFlip,
+    /// exchanges a selected item for a not selected one, if it increases the cost
+    Swap,
+    /// drops the least efficient items until the individual fits,
+    /// then adds the most efficient ones, that still fit
+    DropAdd,
+}
+
+impl FromStr for LocalSearchMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one-flip" => Ok(LocalSearchMethod::OneFlip),
+            "swap" => Ok(LocalSearchMethod::Swap),
+            "drop-add" => Ok(LocalSearchMethod::DropAdd),
+            _ => Err(format!(
+                "unknown local search method: {}, expected one of: one-flip, swap, drop-add",
+                s
+            )),
+        }
+    }
+}
+
+/// Local search improving single individuals, used to turn the algorithm into a memetic one.
+///
+/// The individuals are improved after their repair, so the moves keep the side constraints
+/// satisfied: an item is added only if the constraints admit it, and a protected item is not dropped.
+pub struct LocalSearch {
+    method: LocalSearchMethod,
+    // items sorted by their surrogate efficiency, the most efficient first
+    order: Vec<usize>,
+}
+
+// weight and size of the items selected in an individual
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    weight: u64,
+    size: u64,
+}
+
+impl Usage {
+    fn of(individual: &IndividualMut, task: &Task) -> Self {
+        let mut usage = Self { weight: 0, size: 0 };
+        individual
+            .iter()
+            .enumerate()
+            .filter(|(_, &gene)| gene != 0)
+            .for_each(|(item, _)| usage.add(task, item));
+
+        usage
+    }
+
+    fn add(&mut self, task: &Task, item: usize) {
+        self.weight += task.get_weights()[item] as u64;
+        self.size += task.get_sizes()[item] as u64;
+    }
+
+    fn remove(&mut self, task: &Task, item: usize) {
+        self.weight -= task.get_weights()[item] as u64;
+        self.size -= task.get_sizes()[item] as u64;
+    }
+
+    fn is_feasible(&self, task: &Task) -> bool {
+        self.weight <= task.max_weight as u64 && self.size <= task.max_size as u64
+    }
+
+    fn fits(&self, task: &Task, item: usize) -> bool {
+        self.weight + task.get_weights()[item] as u64 <= task.max_weight as u64
+            && self.size + task.get_sizes()[item] as u64 <= task.max_size as u64
+    }
+}
+
+impl LocalSearch {
+    pub fn new(method: LocalSearchMethod, task: &Task) -> Self {
+        Self {
+            method,
+            order: Relaxation::surrogate(task).order().to_vec(),
+        }
+    }
+
+    pub fn improve(&self, individual: &mut IndividualMut, task: &Task, rng: &mut Random) {
+        match self.method {
+            LocalSearchMethod::OneFlip => one_flip(individual, task, rng),
+            LocalSearchMethod::Swap => swap(individual, task, rng),
+            LocalSearchMethod::DropAdd => self.drop_add(individual, task),
+        }
+    }
+
+    fn drop_add(&self, individual: &mut IndividualMut, task: &Task) {
+        let constraints = task.get_constraints();
+        let mut usage = Usage::of(individual, task);
+        // dropping items only lowers the number of the required ones, so the set stays protected
+        let protected = constraints.protected_items(individual.len(), |item| individual[item] != 0);
+
+        for &item in self.order.iter().rev() {
+            if usage.is_feasible(task) {
+                break;
+            }
+            if individual[item] != 0 && !protected[item] {
+                individual[item] = 0;
+                usage.remove(task, item);
+            }
+        }
+
+        for &item in self.order.iter() {
+            if individual[item] == 0
+                && usage.fits(task, item)
+                && constraints.admits(item, |other| individual[other] != 0)
+            {
+                individual[item] = 1;
+                usage.add(task, item);
+            }
+        }
+    }
+}
+
+/// First improvement hill climber in the 1-flip neighbourhood.
+///
+/// A flip is accepted only if it increases the fitness of the individual.
+/// An infeasible individual is worth nothing, so it is improved only by dropping an item,
+/// which makes it feasible with a positive profit. A feasible one is improved by adding an item,
+/// which fits, is admitted by the side constraints and has a positive gain.
+/// The climber stops after a pass over the genome without any improvement.
+fn one_flip(individual: &mut IndividualMut, task: &Task, rng: &mut Random) {
+    let n = individual.len();
+    if n == 0 {
+        return;
+    }
+
+    let constraints = task.get_constraints();
+    let interactions = task.get_interactions();
+    let mut usage = Usage::of(individual, task);
+    let mut satisfied = constraints.violations(|item| individual[item] != 0).total() == 0;
+    let mut profit = task.profit(|item| individual[item]);
+    let offset = rng.gen_range(0, n);
+
+    // each accepted flip increases the fitness, so the passes end
+    loop {
+        let mut improved = false;
+
+        for item in (0..n).map(|i| (i + offset) % n) {
+            // the profit of the item together with its pairs of the selected items
+            let value =
+                task.get_costs()[item] as u64 + interactions.delta(item, |other| individual[other]);
+
+            if individual[item] != 0 && !(satisfied && usage.is_feasible(task)) {
+                let mut dropped = usage;
+                dropped.remove(task, item);
+                let feasible = dropped.is_feasible(task)
+                    && constraints
+                        .violations(|other| other != item && individual[other] != 0)
+                        .total()
+                        == 0;
+                if feasible && profit > value {
+                    individual[item] = 0;
+                    usage = dropped;
+                    profit -= value;
+                    satisfied = true;
+                    improved = true;
+                }
+            } else if individual[item] == 0
+                && satisfied
+                && usage.is_feasible(task)
+                && value > 0
+                && usage.fits(task, item)
+                && constraints.admits(item, |other| individual[other] != 0)
+            {
+                individual[item] = 1;
+                usage.add(task, item);
+                profit += value;
+                improved = true;
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+}
+
+/// First improvement search in the 1-1 swap neighbourhood, which exchanges an item in
+/// for an item out. The neighbourhood is sampled, with at most `n` tries.
+fn swap(individual: &mut IndividualMut, task: &Task, rng: &mut Random) {
+    let n = individual.len();
+    let mut usage = Usage::of(individual, task);
+    if n == 0 || !usage.is_feasible(task) {
+        return;
+    }
+
+    let mut selected: Vec<usize> = (0..n).filter(|&i| individual[i] != 0).collect();
+    let mut unselected: Vec<usize> = (0..n).filter(|&i| individual[i] == 0).collect();
+    if selected.is_empty() || unselected.is_empty() {
+        return;
+    }
+
+    for _ in 0..n {
+        let out_pos = rng.gen_range(0, selected.len());
+        let in_pos = rng.gen_range(0, unselected.len());
+        let (item_out, item_in) = (selected[out_pos], unselected[in_pos]);
+
+        // the profits of the pairs are evaluated incrementally, only for the swapped items
+        let gain = task.get_costs()[item_in] as u64
+            + task.get_interactions().delta(item_in, |other| {
+                (other != item_out) as Float * individual[other]
+            });
+        let loss = task.get_costs()[item_out] as u64
+            + task
+                .get_interactions()
+                .delta(item_out, |other| individual[other]);
+        if gain <= loss {
+            continue;
+        }
+
+        // the exchange keeps the constraints satisfied, if they have been satisfied before
+        let swapped = |item: usize| item == item_in || item != item_out && individual[item] != 0;
+        if !task.get_constraints().is_empty()
+            && task.get_constraints().violations(swapped).total() != 0
+        {
+            continue;
+        }
+
+        usage.remove(task, item_out);
+        if usage.fits(task, item_in) {
+            usage.add(task, item_in);
+            individual[item_out] = 0;
+            individual[item_in] = 1;
+            selected[out_pos] = item_in;
+            unselected[in_pos] = item_out;
+        } else {
+            usage.add(task, item_out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{individual::as_individual_mut, FloatDVector};
+    use crate::fixtures::{random_interactions, random_task, side_constraints};
+    use rand::SeedableRng;
+
+    const ITEMS: usize = 30;
+
+    fn constrained_task(rng: &mut Random) -> Task {
+        random_task(ITEMS, 1..30, 1.0 / 3.0, rng)
+            .with_interactions(random_interactions(ITEMS, 20, rng))
+            .with_constraints(side_constraints())
+    }
+
+    fn satisfied(task: &Task, genes: &[Float]) -> bool {
+        task.get_constraints()
+            .violations(|item| genes[item] != 0)
+            .total()
+            == 0
+    }
+
+    // the fitness used by the evaluation of the population
+    fn fitness(task: &Task, genes: &[Float]) -> u64 {
+        let sum = |values: &FloatDVector| -> usize {
+            (0..ITEMS)
+                .map(|item| (values[item] * genes[item]) as usize)
+                .sum()
+        };
+        let feasible = sum(task.get_weights()) <= task.max_weight
+            && sum(task.get_sizes()) <= task.max_size
+            && satisfied(task, genes);
+
+        feasible as u64 * task.profit(|item| genes[item])
+    }
+
+    #[test]
+    fn repaired_individuals_are_not_worsened() {
+        let mut rng = Random::seed_from_u64(29);
+
+        for &method in &[
+            LocalSearchMethod::OneFlip,
+            LocalSearchMethod::Swap,
+            LocalSearchMethod::DropAdd,
+        ] {
+            for _ in 0..100 {
+                let task = constrained_task(&mut rng);
+                let local_search = LocalSearch::new(method, &task);
+                let density = rng.gen_range(0.1, 0.6);
+                let mut genes: Vec<Float> =
+                    (0..ITEMS).map(|_| rng.gen_bool(density) as Float).collect();
+                task.get_constraints()
+                    .repair(&mut as_individual_mut(&mut genes), &mut rng);
+                let (before, was_satisfied) = (fitness(&task, &genes), satisfied(&task, &genes));
+
+                local_search.improve(&mut as_individual_mut(&mut genes), &task, &mut rng);
+                assert!(fitness(&task, &genes) >= before, "{:?} {:?}", method, genes);
+                if was_satisfied {
+                    assert!(satisfied(&task, &genes), "{:?} {:?}", method, genes);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_flip_accepts_only_improving_flips() {
+        let mut rng = Random::seed_from_u64(29);
+
+        for _ in 0..100 {
+            let task = constrained_task(&mut rng);
+            let local_search = LocalSearch::new(LocalSearchMethod::OneFlip, &task);
+            let mut genes: Vec<Float> = (0..ITEMS).map(|_| rng.gen_bool(0.3) as Float).collect();
+            task.get_constraints()
+                .repair(&mut as_individual_mut(&mut genes), &mut rng);
+            let before = genes.clone();
+            let fitness_before = fitness(&task, &before);
+
+            local_search.improve(&mut as_individual_mut(&mut genes), &task, &mut rng);
+            let fitness_after = fitness(&task, &genes);
+            if genes == before {
+                assert_eq!(fitness_after, fitness_before);
+            } else {
+                assert!(fitness_after > fitness_before, "{:?}", genes);
+            }
+
+            // no single added item improves the result any more
+            if fitness_after > 0 {
+                for item in (0..ITEMS).filter(|&item| genes[item] == 0) {
+                    let mut flipped = genes.clone();
+                    flipped[item] = 1;
+                    assert!(fitness(&task, &flipped) <= fitness_after);
+                }
+            }
+        }
+    }
+}
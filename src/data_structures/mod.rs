@@ -2,8 +2,23 @@ use nalgebra::{DMatrix, DVector};
 use std::error::Error;
 
 pub mod algorithm_configuration;
+pub mod bins;
+pub mod cache;
+pub mod constraints;
+pub mod density;
+pub mod diversity;
+pub mod evaluation;
+pub mod execution;
 pub mod individual;
+pub mod interactions;
+pub mod local_search;
+pub mod packed;
 pub mod population;
+pub mod restart;
+pub mod schedule;
+pub mod selection;
+pub mod solution;
+pub mod statistics;
 pub mod task;
 pub mod utils;
 
@@ -11,4 +26,7 @@ pub mod utils;
 pub type Float = u32;
 pub type FloatDVector = DVector<Float>;
 pub type DynamicResult<T> = Result<T, Box<dyn Error>>;
+/// Random number generator of the algorithm, which can be seeded, so the runs can be repeated.
+pub type Random = rand::rngs::StdRng;
+/// Genes of the population, each column holds the contiguous genes of one individual.
 pub type PopulationMatrix = DMatrix<Float>;
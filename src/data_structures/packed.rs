@@ -0,0 +1,350 @@
+use super::{Float, PopulationMatrix};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+
+/// Number of the individuals packed into a single block, one bit of the mask for each of them.
+pub const LANES: usize = 16;
+
+// one bit for each lane of a block
+type Mask = u16;
+
+/// Binary genes of the population, packed into blocks of `LANES` individuals.
+///
+/// Each block holds one mask for each item, with the bits of the individuals selecting it,
+/// so the kernel sums the values of an item for all the individuals of the block at once.
+#[derive(Debug, Clone)]
+pub struct PackedPopulation {
+    items: usize,
+    individuals: usize,
+    masks: Vec<Mask>,
+}
+
+impl PackedPopulation {
+    /// Packs the population, any non zero gene selects its item.
+    pub fn pack(population: &PopulationMatrix, parallel: bool) -> Self {
+        let (items, individuals) = population.shape();
+        let blocks = individuals.div_ceil(LANES);
+        let genes = population.as_slice();
+        let mut masks = vec![0 as Mask; blocks * items];
+
+        // each individual sets its bit in all the masks of the block in a single, vectorized pass
+        let pack = |(block, masks): (usize, &mut [Mask])| {
+            for lane in 0..LANES.min(individuals - block * LANES) {
+                let individual = block * LANES + lane;
+                let genes = &genes[individual * items..(individual + 1) * items];
+                for (mask, &gene) in masks.iter_mut().zip(genes.iter()) {
+                    *mask |= ((gene != 0) as Mask) << lane;
+                }
+            }
+        };
+
+        if parallel {
+            masks
+                .par_chunks_mut(items.max(1))
+                .enumerate()
+                .for_each(pack);
+        } else {
+            masks.chunks_mut(items.max(1)).enumerate().for_each(pack);
+        }
+
+        Self {
+            items,
+            individuals,
+            masks,
+        }
+    }
+
+    pub fn individuals(&self) -> usize {
+        self.individuals
+    }
+
+    /// Sums of the weights, sizes and costs of the items selected by each individual.
+    /// The sums saturate at the largest value of a gene, as the sums of the dense back-end.
+    ///
+    /// Uses the vector instructions of the processor, if they are available.
+    pub fn masked_sums(
+        &self,
+        weights: &[Float],
+        sizes: &[Float],
+        costs: &[Float],
+        parallel: bool,
+    ) -> Vec<(Float, Float, Float)> {
+        self.sums_with(weights, sizes, costs, parallel, block_sums)
+    }
+
+    /// Same as `masked_sums`, but compiled only for the instructions available on every processor.
+    pub fn masked_sums_scalar(
+        &self,
+        weights: &[Float],
+        sizes: &[Float],
+        costs: &[Float],
+        parallel: bool,
+    ) -> Vec<(Float, Float, Float)> {
+        self.sums_with(weights, sizes, costs, parallel, block_sums_scalar)
+    }
+
+    fn sums_with(
+        &self,
+        weights: &[Float],
+        sizes: &[Float],
+        costs: &[Float],
+        parallel: bool,
+        kernel: BlockKernel,
+    ) -> Vec<(Float, Float, Float)> {
+        assert!(weights.len() == self.items && sizes.len() == self.items);
+        assert!(costs.len() == self.items);
+
+        let blocks = self.individuals.div_ceil(LANES);
+        let mut sums = vec![(0 as Float, 0 as Float, 0 as Float); blocks * LANES];
+        let sum = |(masks, sums): (&[Mask], &mut [(Float, Float, Float)])| {
+            let [weight, size, cost] = kernel(masks, weights, sizes, costs);
+            for (lane, sums) in sums.iter_mut().enumerate() {
+                *sums = (weight[lane], size[lane], cost[lane]);
+            }
+        };
+
+        if parallel {
+            self.masks
+                .par_chunks(self.items.max(1))
+                .zip(sums.par_chunks_mut(LANES))
+                .for_each(sum);
+        } else {
+            self.masks
+                .chunks(self.items.max(1))
+                .zip(sums.chunks_mut(LANES))
+                .for_each(sum);
+        }
+
+        sums.truncate(self.individuals);
+        sums
+    }
+}
+
+// sums of the weights, sizes and costs of each lane of a block
+type BlockSums = [[Float; LANES]; 3];
+type BlockKernel = fn(&[Mask], &[Float], &[Float], &[Float]) -> BlockSums;
+
+// chooses the kernel compiled for the best instructions supported by the processor
+fn block_sums(masks: &[Mask], weights: &[Float], sizes: &[Float], costs: &[Float]) -> BlockSums {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // safe, as the processor supports avx2
+            return unsafe { block_sums_avx2(masks, weights, sizes, costs) };
+        }
+    }
+
+    block_sums_scalar(masks, weights, sizes, costs)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn block_sums_avx2(
+    masks: &[Mask],
+    weights: &[Float],
+    sizes: &[Float],
+    costs: &[Float],
+) -> BlockSums {
+    block_sums_generic(masks, weights, sizes, costs)
+}
+
+fn block_sums_scalar(
+    masks: &[Mask],
+    weights: &[Float],
+    sizes: &[Float],
+    costs: &[Float],
+) -> BlockSums {
+    block_sums_generic(masks, weights, sizes, costs)
+}
+
+// the lanes are independent, so the compiler turns the inner loop into vector instructions,
+// adding the values of the item masked by the bits of the lanes selecting it.
+// The values are not negative, so saturating each addition equals saturating the whole sum
+#[inline(always)]
+fn block_sums_generic(
+    masks: &[Mask],
+    weights: &[Float],
+    sizes: &[Float],
+    costs: &[Float],
+) -> BlockSums {
+    let [mut weight_sums, mut size_sums, mut cost_sums]: BlockSums = [[0; LANES]; 3];
+
+    for (((&mask, &weight), &size), &cost) in masks
+        .iter()
+        .zip(weights.iter())
+        .zip(sizes.iter())
+        .zip(costs.iter())
+    {
+        for (lane, ((weight_sum, size_sum), cost_sum)) in weight_sums
+            .iter_mut()
+            .zip(size_sums.iter_mut())
+            .zip(cost_sums.iter_mut())
+            .enumerate()
+        {
+            // all ones, if the lane selects the item, zero otherwise
+            let keep = (0 as Float).wrapping_sub(((mask >> lane) & 1) as Float);
+            *weight_sum = weight_sum.saturating_add(weight & keep);
+            *size_sum = size_sum.saturating_add(size & keep);
+            *cost_sum = cost_sum.saturating_add(cost & keep);
+        }
+    }
+
+    [weight_sums, size_sums, cost_sums]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{
+        evaluation::EvaluationBackend, interactions::Interactions, population::Population,
+        task::Task, utils::saturate, FloatDVector, Random,
+    };
+    use crate::fixtures::{random_interactions, random_pairs, random_task};
+    use rand::{Rng, SeedableRng};
+
+    fn random_population(
+        items: usize,
+        individuals: usize,
+        density: f64,
+        rng: &mut Random,
+    ) -> PopulationMatrix {
+        PopulationMatrix::from_fn(items, individuals, |_, _| rng.gen_bool(density) as Float)
+    }
+
+    fn random_values(items: usize, rng: &mut Random) -> Vec<Float> {
+        (0..items).map(|_| rng.gen_range(1, 1000)).collect()
+    }
+
+    // sums of the values of the selected items, computed gene by gene in u64
+    fn expected_sums(
+        population: &PopulationMatrix,
+        weights: &[Float],
+        sizes: &[Float],
+        costs: &[Float],
+    ) -> Vec<(Float, Float, Float)> {
+        population
+            .column_iter()
+            .map(|genes| {
+                genes
+                    .iter()
+                    .enumerate()
+                    .fold((0u64, 0u64, 0u64), |sums, (item, &gene)| {
+                        (
+                            sums.0 + gene as u64 * weights[item] as u64,
+                            sums.1 + gene as u64 * sizes[item] as u64,
+                            sums.2 + gene as u64 * costs[item] as u64,
+                        )
+                    })
+            })
+            .map(|(weight, size, cost)| (saturate(weight), saturate(size), saturate(cost)))
+            .collect()
+    }
+
+    #[test]
+    fn kernels_sum_the_selected_items() {
+        let mut rng = Random::seed_from_u64(47);
+
+        // the shapes include partial blocks, and single and missing items and individuals
+        for &(items, individuals) in
+            [(0, 5), (1, 1), (7, 0), (100, 16), (101, 37), (333, 130)].iter()
+        {
+            let density = rng.gen_range(0.01, 0.9);
+            let population = random_population(items, individuals, density, &mut rng);
+            let weights = random_values(items, &mut rng);
+            let sizes = random_values(items, &mut rng);
+            let costs = random_values(items, &mut rng);
+            let expected = expected_sums(&population, &weights, &sizes, &costs);
+
+            for &parallel in [false, true].iter() {
+                let packed = PackedPopulation::pack(&population, parallel);
+                assert_eq!(packed.individuals(), individuals);
+                assert_eq!(
+                    packed.masked_sums(&weights, &sizes, &costs, parallel),
+                    expected
+                );
+                assert_eq!(
+                    packed.masked_sums_scalar(&weights, &sizes, &costs, parallel),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn packed_evaluation_equals_dense_evaluation() {
+        let mut rng = Random::seed_from_u64(48);
+        let items = 203;
+        let task = random_task(items, 1..1000, 1.0 / 3.0, &mut rng);
+        let interacting = task
+            .clone()
+            .with_interactions(random_interactions(items, 400, &mut rng));
+
+        for task in [task, interacting].iter() {
+            let mut population = Population::from(random_population(items, 75, 0.2, &mut rng));
+            for &parallel in [false, true].iter() {
+                population.set_parallel(parallel);
+                population.set_evaluation_backend(EvaluationBackend::Dense);
+                let dense = population.evaluate(task);
+                assert!(dense.iter().any(|&cost| cost > 0));
+                let dense_sums = population.resource_sums(task);
+
+                for &backend in [EvaluationBackend::Packed, EvaluationBackend::Sparse].iter() {
+                    population.set_evaluation_backend(backend);
+                    assert_eq!(population.evaluate(task), dense);
+                    assert_eq!(population.resource_sums(task), dense_sums);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sums_near_the_largest_value_equal_dense_sums() {
+        let mut rng = Random::seed_from_u64(47);
+        let items = 24;
+        // a few of the items exceed the largest value, the sums of the others just fit
+        let large = |rng: &mut Random| -> Vec<Float> {
+            (0..items)
+                .map(|_| rng.gen_range(Float::MAX / 16, Float::MAX / 8))
+                .collect()
+        };
+        let (weights, sizes, costs) = (large(&mut rng), large(&mut rng), large(&mut rng));
+        let population = random_population(items, 40, 0.3, &mut rng);
+        let expected = expected_sums(&population, &weights, &sizes, &costs);
+        assert!(expected.iter().any(|sums| sums.0 == Float::MAX));
+        assert!(expected
+            .iter()
+            .any(|sums| sums.0 < Float::MAX && sums.0 > Float::MAX / 2));
+
+        let packed = PackedPopulation::pack(&population, false);
+        assert_eq!(
+            packed.masked_sums(&weights, &sizes, &costs, false),
+            expected
+        );
+        assert_eq!(
+            packed.masked_sums_scalar(&weights, &sizes, &costs, false),
+            expected
+        );
+
+        let pairs = random_pairs(items, 30, Float::MAX / 64..Float::MAX / 32, &mut rng);
+        let vector = |values: Vec<Float>| FloatDVector::from_vec(values);
+        let task = Task::from_args(
+            items,
+            usize::MAX,
+            usize::MAX,
+            vector(weights),
+            vector(sizes),
+            vector(costs),
+        )
+        .with_interactions(Interactions::new(items, &pairs).unwrap());
+
+        let mut population = Population::from(population);
+        population.set_evaluation_backend(EvaluationBackend::Dense);
+        let dense = population.resource_sums(&task);
+        for &backend in [EvaluationBackend::Packed, EvaluationBackend::Sparse].iter() {
+            population.set_evaluation_backend(backend);
+            assert_eq!(population.resource_sums(&task), dense);
+        }
+    }
+}
@@ -1,21 +1,130 @@
 use super::{
-    individual::{Crossover, Mutate},
+    bins::{self, Bin},
+    cache::{genome_hash, FitnessCache},
+    constraints::{Constraints, Violations},
+    diversity::{self, Diversity, DiversityMethod},
+    evaluation::{self, EvaluationBackend},
+    execution::derived_rng,
+    individual::{
+        as_individual, as_individual_mut, Crossover, Individual, IndividualMut, IntegerCrossover,
+        IntegerMutation, Mutate,
+    },
+    local_search::LocalSearch,
+    packed::PackedPopulation,
+    schedule::MAX_RATE,
+    selection::Tournament,
+    statistics::GenerationStatistics,
     task::Task,
-    utils::get_sparse_DVec_with,
-    DynamicResult, Float, FloatDVector, PopulationMatrix,
+    utils::saturate,
+    DynamicResult, Float, FloatDVector, PopulationMatrix, Random,
 };
-use crossbeam::thread;
-use nalgebra::{base::dimension::Dim, DMatrix};
+use nalgebra::base::dimension::Dim;
 use rand::{
-    distributions::{Distribution, Uniform},
-    prelude::ThreadRng,
-    thread_rng,
+    distributions::{Bernoulli, Distribution},
+    seq::{index, SliceRandom},
+    Rng,
 };
-use rayon::iter::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use rand_distr::StandardNormal;
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+use std::collections::{hash_map::Entry, HashMap};
+use std::mem;
+
+/// Parameters of the operators used to breed a single generation.
+pub struct GenerationParameters<'a> {
+    pub tournament: Tournament,
+    pub crossover_rate: f64,
+    /// if not set, a random cutting point is drawn for each crossover
+    pub cutting_point: Option<usize>,
+    pub mutation_rate: f64,
+    /// local search applied to the offspring, if the algorithm should be a memetic one
+    pub local_search: Option<&'a LocalSearch>,
+    /// fraction of the offspring improved with the local search
+    pub local_search_rate: f64,
+    /// if set, the offspring inherit and perturb the rates of their parents,
+    /// instead of using `crossover_rate` and `mutation_rate`
+    pub self_adaptive: bool,
+    /// operators used instead of the binary ones, if the task has integer genes
+    pub integer_mutation: IntegerMutation,
+    pub integer_crossover: IntegerCrossover,
+    /// mechanism keeping the population diverse, if any
+    pub diversity: Option<Diversity>,
+    /// diversity of the population, if the caller has already measured it in this generation,
+    /// so it is not measured again
+    pub population_diversity: Option<f64>,
+}
+
+/// Mutation and crossover rates carried by an individual, when they are self-adaptive.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rates {
+    pub mutation: f64,
+    pub crossover: f64,
+}
+
+impl Rates {
+    // derives the rates of a child from the rates of its parents, with a log-normal perturbation
+    fn inherit(first: Rates, second: Rates, min_mutation: f64, rng: &mut Random) -> Self {
+        let perturbation = |rng: &mut Random| {
+            let noise: f64 = StandardNormal.sample(rng);
+            (SELF_ADAPTATION_LEARNING_RATE * noise).exp()
+        };
+
+        Self {
+            mutation: ((first.mutation + second.mutation) / 2f64 * perturbation(rng))
+                .clamp(min_mutation, MAX_RATE),
+            crossover: ((first.crossover + second.crossover) / 2f64 * perturbation(rng))
+                .clamp(0f64, 1f64),
+        }
+    }
+}
+
+// learning rate of the log-normal perturbation of the self-adaptive rates
+const SELF_ADAPTATION_LEARNING_RATE: f64 = 0.22;
+
+// how an individual has been bred
+#[derive(Debug, Clone, Copy, Default)]
+struct Origin {
+    // fitness of the better parent
+    parent_fitness: Float,
+    rates: Rates,
+    // profit of the pairs of items, if the profits of the parents are known
+    pair_profit: Float,
+}
 
 #[derive(Debug)]
 pub struct Population {
+    // each column holds the genes of one individual
     population: PopulationMatrix,
+    // rates of each individual, empty unless they are self-adaptive
+    rates: Vec<Rates>,
+    // origins of each individual, empty in the initial population
+    origins: Vec<Origin>,
+    // the best individual seen so far
+    elite: Option<Elite>,
+    // children of the next generation, swapped with the population after breeding them,
+    // so both matrices are reused by every generation
+    offspring: PopulationMatrix,
+    // if set, the individuals are evaluated and bred across the threads of the pool
+    parallel: bool,
+    // how the selected items of the individuals are summed
+    evaluation: EvaluationBackend,
+    // fitness of the genomes evaluated by the previous generations, if it is remembered
+    cache: Option<FitnessCache>,
+    // evaluation of the population and the hit rate of the cache, if they are known
+    // from the replacement of the parents by the previous generation
+    survivors: Option<(FloatDVector, Option<f64>)>,
+    // profits of the pairs of items of each individual, if they are known from the breeding,
+    // so the evaluation does not sum all the pairs again
+    profits: Option<Vec<Float>>,
+}
+
+/// The best individual seen during the run.
+#[derive(Debug, Clone)]
+pub struct Elite {
+    pub fitness: Float,
+    pub genome: Vec<Float>,
 }
 
 impl Population {
@@ -23,209 +132,1260 @@ impl Population {
     pub fn generate_initial_population(
         population_size: usize,
         number_of_elements: usize,
-        population_density: usize,
+        ones_probability: f64,
+        rng: &mut Random,
     ) -> Self {
-        let dist = Uniform::from(0..population_density as Float);
+        let dist = Bernoulli::new(ones_probability).expect("probability out of range [0, 1]");
 
-        let matrix: Vec<Float> = (0..number_of_elements * population_size)
-            .into_par_iter()
-            .map_init(|| thread_rng(), |rng, _| (dist.sample(rng) == 1) as Float)
-            .collect();
-
-        let matrix = PopulationMatrix::from_vec_generic(
-            Dim::from_usize(population_size),
+        let mut population = Self::from(PopulationMatrix::from_element_generic(
             Dim::from_usize(number_of_elements),
-            matrix,
-        );
+            Dim::from_usize(population_size),
+            0,
+        ));
+        population.for_each_individual(rng, |mut individual, rng| {
+            individual
+                .iter_mut()
+                .for_each(|gene| *gene = dist.sample(rng) as Float)
+        });
+
+        population
+    }
+
+    /// Evaluates and breeds the individuals one by one, if `parallel` is not set,
+    /// instead of spreading them across the threads of the pool.
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+
+    /// Chooses between summing all the genes of the individuals and summing only their selected items.
+    pub fn set_evaluation_backend(&mut self, evaluation: EvaluationBackend) {
+        self.evaluation = evaluation;
+    }
+
+    /// Remembers the fitness of at most `size` genomes, so the duplicates of the genomes
+    /// evaluated before are not evaluated again. The cache is disabled, if `size` is zero.
+    pub fn enable_fitness_cache(&mut self, size: usize) {
+        self.cache = if size > 0 {
+            Some(FitnessCache::new(size))
+        } else {
+            None
+        };
+    }
+
+    /// Replaces each selected gene with a number drawn uniformly up to the bound of the gene:
+    /// the number of the copies of the item, or the number of its bin.
+    pub fn draw_copies(&mut self, upper_bounds: &[Float], rng: &mut Random) {
+        self.for_each_individual(rng, |mut individual, rng| {
+            individual
+                .iter_mut()
+                .zip(upper_bounds.iter())
+                .filter(|(gene, &bound)| **gene != 0 && bound > 0)
+                .for_each(|(gene, &bound)| *gene = rng.gen_range(1, bound + 1));
+        });
+    }
 
-        Self { population: matrix }
+    // changes each individual with its own generator, derived from a seed drawn from `rng`,
+    // so the result does not depend on the threads, which run it
+    fn for_each_individual<'a, F>(&'a mut self, rng: &mut Random, change: F)
+    where
+        F: Fn(IndividualMut<'a>, &mut Random) + Sync + Send,
+    {
+        self.for_each_indexed_individual(rng, |_, individual, rng| change(individual, rng));
+    }
+
+    // same as `for_each_individual`, but the index of the individual is passed to `change`
+    fn for_each_indexed_individual<'a, F>(&'a mut self, rng: &mut Random, change: F)
+    where
+        F: Fn(usize, IndividualMut<'a>, &mut Random) + Sync + Send,
+    {
+        self.survivors = None;
+        self.profits = None;
+        let seed: u64 = rng.gen();
+        let cols = self.number_of_genes().max(1);
+        let change = |(index, genes): (usize, &'a mut [Float])| {
+            change(
+                index,
+                as_individual_mut(genes),
+                &mut derived_rng(seed, index),
+            )
+        };
+
+        if self.parallel {
+            self.population
+                .as_mut_slice()
+                .par_chunks_mut(cols)
+                .enumerate()
+                .for_each(change);
+        } else {
+            self.population
+                .as_mut_slice()
+                .chunks_mut(cols)
+                .enumerate()
+                .for_each(change);
+        }
+    }
+
+    /// Makes each individual carry its own mutation and crossover rates, starting from `rates`.
+    pub fn enable_self_adaptation(&mut self, rates: Rates) {
+        self.rates = vec![rates; self.number_of_individuals()];
+    }
+
+    /// Mean rates of the individuals, if they are self-adaptive.
+    pub fn mean_rates(&self) -> Option<Rates> {
+        if self.rates.is_empty() {
+            return None;
+        }
+
+        let n = self.rates.len() as f64;
+        Some(Rates {
+            mutation: self.rates.iter().map(|r| r.mutation).sum::<f64>() / n,
+            crossover: self.rates.iter().map(|r| r.crossover).sum::<f64>() / n,
+        })
+    }
+
+    /// Mean Hamming distance between two different individuals,
+    /// normalized by the number of genes, so it is in range [0, 1].
+    pub fn diversity(&self) -> f64 {
+        let (rows, cols) = (self.number_of_individuals(), self.number_of_genes());
+        if rows < 2 || cols == 0 {
+            return 0f64;
+        }
+
+        let mut ones = vec![0usize; cols];
+        for genes in self.population.as_slice().chunks(cols) {
+            ones.iter_mut()
+                .zip(genes.iter())
+                .for_each(|(ones, &gene)| *ones += (gene != 0) as usize);
+        }
+
+        // each gene contributes the number of pairs of individuals, which differ on it
+        let differing_pairs: f64 = ones
+            .iter()
+            .map(|&ones| ones as f64 * (rows as f64 - ones as f64))
+            .sum();
+        let pairs = rows as f64 * (rows as f64 - 1f64) / 2f64;
+
+        differing_pairs / pairs / cols as f64
     }
 
     pub fn evaluate(&self, task: &Task) -> FloatDVector {
-        thread::scope(|s| {
-            let sum_weights_thread = s.spawn(|_| {
-                Self::get_evaluated_vec(&self.population, task.get_weights(), task.max_weight)
+        if let Some(bins) = task.get_bins() {
+            return self.evaluate_bins(task, bins);
+        }
+
+        let (max_weight, max_size) = (task.max_weight as Float, task.max_size as Float);
+        let sums = self.individual_sums(task);
+        let mut costs_vec = FloatDVector::from_iterator(
+            sums.len(),
+            sums.iter().map(|&(weight, size, cost)| {
+                cost * (weight <= max_weight && size <= max_size) as Float
+            }),
+        );
+        if !task.get_constraints().is_empty() {
+            costs_vec.component_mul_assign(&Self::get_constraints_vec(
+                &self.population,
+                task.get_constraints(),
+            ));
+        }
+
+        costs_vec
+    }
+
+    // evaluates only the individuals, whose fitness is not remembered by the cache,
+    // along with the fraction of the individuals, which have not been evaluated
+    fn evaluate_cached(&mut self, task: &Task) -> (FloatDVector, Option<f64>) {
+        let mut cache = match self.cache.take() {
+            Some(cache) => cache,
+            None => return (self.evaluate(task), None),
+        };
+
+        let cols = self.number_of_genes().max(1);
+        let genes = self.population.as_slice();
+        let hashes: Vec<u64> = if self.parallel {
+            genes.par_chunks(cols).map(genome_hash).collect()
+        } else {
+            genes.chunks(cols).map(genome_hash).collect()
+        };
+
+        let mut evaluation = FloatDVector::zeros(self.number_of_individuals());
+        // the individuals to evaluate, the copies of a missing genome are evaluated once
+        let mut missing: Vec<usize> = Vec::new();
+        let mut copies: Vec<(usize, usize)> = Vec::new();
+        let mut missing_hashes: HashMap<u64, usize> = HashMap::new();
+        for (individual, &hash) in hashes.iter().enumerate() {
+            if let Some(fitness) = cache.get(hash) {
+                evaluation[individual] = fitness;
+                continue;
+            }
+
+            match missing_hashes.entry(hash) {
+                Entry::Occupied(entry) => copies.push((individual, *entry.get())),
+                Entry::Vacant(entry) => {
+                    entry.insert(missing.len());
+                    missing.push(individual);
+                }
+            }
+        }
+
+        let fitness = if missing.len() == self.number_of_individuals() {
+            self.evaluate(task)
+        } else {
+            let genomes = PopulationMatrix::from_iterator(
+                self.number_of_genes(),
+                missing.len(),
+                missing
+                    .iter()
+                    .flat_map(|&individual| {
+                        genes[individual * cols..][..self.number_of_genes()].iter()
+                    })
+                    .cloned(),
+            );
+            let mut subset = Self::from(genomes);
+            subset.parallel = self.parallel;
+            subset.evaluation = self.evaluation;
+            subset.profits = self.profits.as_ref().map(|profits| {
+                missing
+                    .iter()
+                    .map(|&individual| profits[individual])
+                    .collect()
             });
-            let sum_sizes_thread = s.spawn(|_| {
-                Self::get_evaluated_vec(&self.population, task.get_sizes(), task.max_size)
+            subset.evaluate(task)
+        };
+        for (&individual, &fitness) in missing.iter().zip(fitness.iter()) {
+            evaluation[individual] = fitness;
+            cache.insert(hashes[individual], fitness);
+        }
+        for &(individual, original) in copies.iter() {
+            evaluation[individual] = fitness[original];
+        }
+
+        let hit_rate = 1f64 - missing.len() as f64 / self.number_of_individuals().max(1) as f64;
+        self.cache = Some(cache);
+
+        (evaluation, Some(hit_rate))
+    }
+
+    // weight, size and cost of each individual, including the profits of the pairs of items.
+    // Each individual is contiguous, so they are summed in a single pass over its genes,
+    // over the list of its selected items, or over the bits of the packed genes.
+    // The sums saturate at the largest value of a gene, so all the back-ends agree on them
+    fn individual_sums(&self, task: &Task) -> Vec<(Float, Float, Float)> {
+        let cols = self.number_of_genes().max(1);
+        let weights = task.get_weights().as_slice();
+        let sizes = task.get_sizes().as_slice();
+        let costs = task.get_costs().as_slice();
+        let interactions = task.get_interactions();
+        let genes = self.population.as_slice();
+        let known_profits = self
+            .profits
+            .as_deref()
+            .filter(|profits| profits.len() == self.number_of_individuals());
+        let sums_pairs = !interactions.is_empty() && known_profits.is_none();
+
+        let mut sums: Vec<(Float, Float, Float)> =
+            match self.evaluation.resolve(&self.population, task) {
+                EvaluationBackend::Packed => {
+                    let packed = PackedPopulation::pack(&self.population, self.parallel);
+                    let mut sums = packed.masked_sums(weights, sizes, costs, self.parallel);
+                    if sums_pairs {
+                        let add_profit = |(sums, genes): (&mut (Float, Float, Float), &[Float])| {
+                            sums.2 = sums.2.saturating_add(interactions.dense_profit(genes))
+                        };
+                        if self.parallel {
+                            sums.par_iter_mut()
+                                .zip(genes.par_chunks(cols))
+                                .for_each(add_profit);
+                        } else {
+                            sums.iter_mut().zip(genes.chunks(cols)).for_each(add_profit);
+                        }
+                    }
+
+                    sums
+                }
+                EvaluationBackend::Sparse => {
+                    // the list of the selected items is reused by all the individuals summed by a thread
+                    let sums = |selected: &mut Vec<usize>, genes: &[Float]| {
+                        evaluation::selected_items(genes, selected);
+                        let (mut weight, mut size, mut cost) = (0u64, 0u64, 0u64);
+                        for &item in selected.iter() {
+                            weight += genes[item] as u64 * weights[item] as u64;
+                            size += genes[item] as u64 * sizes[item] as u64;
+                            cost += genes[item] as u64 * costs[item] as u64;
+                        }
+                        if sums_pairs {
+                            cost += interactions.sparse_profit(genes, selected) as u64;
+                        }
+                        (saturate(weight), saturate(size), saturate(cost))
+                    };
+
+                    if self.parallel {
+                        genes.par_chunks(cols).map_init(Vec::new, sums).collect()
+                    } else {
+                        let mut selected = Vec::new();
+                        genes
+                            .chunks(cols)
+                            .map(|genes| sums(&mut selected, genes))
+                            .collect()
+                    }
+                }
+                // dense
+                _ => {
+                    let sums = |genes: &[Float]| {
+                        let (mut weight, mut size, mut cost) = (0u64, 0u64, 0u64);
+                        for (i, &gene) in genes.iter().enumerate() {
+                            weight += gene as u64 * weights[i] as u64;
+                            size += gene as u64 * sizes[i] as u64;
+                            cost += gene as u64 * costs[i] as u64;
+                        }
+                        if sums_pairs {
+                            cost += interactions.dense_profit(genes) as u64;
+                        }
+                        (saturate(weight), saturate(size), saturate(cost))
+                    };
+
+                    if self.parallel {
+                        genes.par_chunks(cols).map(sums).collect()
+                    } else {
+                        genes.chunks(cols).map(sums).collect()
+                    }
+                }
+            };
+
+        if let Some(profits) = known_profits {
+            sums.iter_mut()
+                .zip(profits.iter())
+                .for_each(|(sums, &profit)| sums.2 = sums.2.saturating_add(profit));
+        }
+
+        sums
+    }
+
+    // profit of the pairs of items of each individual, summed over all the pairs
+    fn pair_profits(&self, task: &Task) -> Vec<Float> {
+        let cols = self.number_of_genes().max(1);
+        let interactions = task.get_interactions();
+        let genes = self.population.as_slice();
+
+        if self.parallel {
+            genes
+                .par_chunks(cols)
+                .map(|genes| interactions.dense_profit(genes))
+                .collect()
+        } else {
+            genes
+                .chunks(cols)
+                .map(|genes| interactions.dense_profit(genes))
+                .collect()
+        }
+    }
+
+    // the genes hold the numbers of the bins, so the costs are summed over the assigned items,
+    // and each bin is checked against its own capacities
+    fn evaluate_bins(&self, task: &Task, bins: &[Bin]) -> FloatDVector {
+        let assigned = self.population.map(|gene| (gene != 0) as Float);
+
+        let mut costs_vec = Self::get_summed_vec(&assigned, task.get_costs());
+        if !task.get_interactions().is_empty() {
+            costs_vec += task.get_interactions().evaluate(&assigned);
+        }
+        costs_vec.component_mul_assign(&bins::get_capacity_vec(&self.population, task, bins));
+        if !task.get_constraints().is_empty() {
+            costs_vec.component_mul_assign(&Self::get_constraints_vec(
+                &self.population,
+                task.get_constraints(),
+            ));
+        }
+
+        costs_vec
+    }
+
+    pub fn evolve_generation(
+        &mut self,
+        task: &Task,
+        parameters: &GenerationParameters,
+        rng: &mut Random,
+    ) -> DynamicResult<GenerationStatistics> {
+        // the profits of the pairs of items are summed once, the children derive them from their parents
+        let derives_profits = !task.get_interactions().is_empty() && task.get_bins().is_none();
+        if derives_profits && self.profits.is_none() {
+            self.profits = Some(self.pair_profits(task));
+        }
+
+        let (evaluation, cache_hit_rate) = match self.survivors.take() {
+            Some(survivors) => survivors,
+            None => self.evaluate_cached(task),
+        };
+
+        let (best_idx, best) = evaluation.argmax();
+        self.update_elite(best_idx, best);
+        let success_ratio = self.success_ratio(&evaluation);
+        let population_diversity = parameters
+            .population_diversity
+            .unwrap_or_else(|| self.diversity());
+
+        let self_adaptive = parameters.self_adaptive && !self.rates.is_empty();
+        let min_mutation_rate = (1f64 / self.number_of_genes().max(1) as f64).min(MAX_RATE);
+        let rows = self.number_of_individuals();
+        let cols = self.number_of_genes();
+        let method = parameters.diversity.map(|diversity| diversity.method);
+
+        // the parents are selected by their shared fitness,
+        // while the children are compared with the raw one
+        let shared_fitness = match parameters.diversity {
+            Some(diversity) if diversity.method == DiversityMethod::Sharing => {
+                Some(diversity::shared_fitness(
+                    &self.population,
+                    &evaluation,
+                    diversity.sharing_radius,
+                    self.parallel,
+                ))
+            }
+            _ => None,
+        };
+        let selection_fitness = shared_fitness.as_ref().unwrap_or(&evaluation);
+        // the crowding breeds two children from each pair of parents, drawn without replacement
+        let pairing: Option<Vec<usize>> = if method == Some(DiversityMethod::Crowding) {
+            let mut order: Vec<usize> = (0..rows).collect();
+            order.shuffle(rng);
+            Some(order)
+        } else {
+            None
+        };
+        self.origins.resize(rows, Origin::default());
+        // allocated once, the children overwrite all the genes of the previous generation
+        if self.offspring.shape() != self.population.shape() {
+            self.offspring = PopulationMatrix::zeros(cols, rows);
+        }
+
+        let Self {
+            population,
+            rates,
+            origins,
+            offspring,
+            parallel,
+            profits,
+            ..
+        } = self;
+        let population: &PopulationMatrix = population;
+        let rates: &[Rates] = rates;
+        let profits: Option<&[Float]> = profits.as_deref();
+        let gene_bounds = task.get_gene_bounds();
+        let seed: u64 = rng.gen();
+
+        let breed = |(index, (genes, origin)): (usize, (&mut [Float], &mut Origin))| {
+            let rng = &mut derived_rng(seed, index);
+            let individual = &mut as_individual_mut(genes);
+            let (first_parent_idx, second_parent_idx) = match &pairing {
+                Some(order) => (order[index], order[mate(index, rows)]),
+                None => (
+                    parameters.tournament.select(selection_fitness, rng),
+                    parameters.tournament.select(selection_fitness, rng),
+                ),
+            };
+            let first_parent = Self::individual_of(population, first_parent_idx);
+            let second_parent = Self::individual_of(population, second_parent_idx);
+            origin.parent_fitness = evaluation[first_parent_idx].max(evaluation[second_parent_idx]);
+
+            let child_rates = if self_adaptive {
+                Rates::inherit(
+                    rates[first_parent_idx],
+                    rates[second_parent_idx],
+                    min_mutation_rate,
+                    rng,
+                )
+            } else {
+                Rates {
+                    mutation: parameters.mutation_rate,
+                    crossover: parameters.crossover_rate,
+                }
+            };
+
+            let arithmetic = gene_bounds.is_some()
+                && parameters.integer_crossover == IntegerCrossover::Arithmetic;
+            // both children of a pair of the crowding draw the same crossover,
+            // so they complement each other
+            let mut pair_rng = pairing
+                .as_ref()
+                .map(|_| derived_rng(seed, rows + index / 2));
+            let crossover_rng = pair_rng.as_mut().unwrap_or(&mut *rng);
+            match parameters.cutting_point {
+                _ if arithmetic => individual.arithmetic_crossover(
+                    first_parent,
+                    second_parent,
+                    child_rates.crossover,
+                    crossover_rng,
+                ),
+                Some(cutting_point) => individual.crossover(
+                    first_parent,
+                    second_parent,
+                    child_rates.crossover,
+                    cutting_point,
+                    crossover_rng,
+                ),
+                None => individual.random_crossover(
+                    first_parent,
+                    second_parent,
+                    child_rates.crossover,
+                    crossover_rng,
+                ),
+            }
+            match gene_bounds {
+                Some(gene_bounds) => individual.mutate_integer(
+                    child_rates.mutation,
+                    gene_bounds,
+                    parameters.integer_mutation,
+                    rng,
+                ),
+                None => individual.mutate(child_rates.mutation, rng),
+            }
+
+            task.get_constraints().repair(individual, rng);
+            if let Some(bins) = task.get_bins() {
+                bins::repair(individual, task, bins, rng);
+            }
+            // the local search improves the repaired child, so it keeps the constraints satisfied
+            if let Some(local_search) = parameters.local_search {
+                if rng.gen_bool(parameters.local_search_rate) {
+                    local_search.improve(individual, task, rng);
+                }
+            }
+
+            origin.rates = child_rates;
+            origin.pair_profit = profits.map_or(0, |profits| {
+                task.get_interactions().child_profit(
+                    Self::genes_of(population, first_parent_idx),
+                    profits[first_parent_idx],
+                    genes,
+                )
             });
-            let sum_costs_thread =
-                s.spawn(|_| Self::get_summed_vec(&self.population, task.get_costs()));
-
-            // TODO: come up with some more useful panic messages!
-            let mut costs_vec = sum_costs_thread.join().expect("sum_costs_thread panicked");
-
-            costs_vec
-                .component_mul_assign(&sum_sizes_thread.join().expect("sum_sizes_thread panicked"));
-            costs_vec.component_mul_assign(
-                &sum_weights_thread
-                    .join()
-                    .expect("sum_weights_thread panicked"),
-            );
+        };
+
+        if *parallel {
+            offspring
+                .as_mut_slice()
+                .par_chunks_mut(cols.max(1))
+                .zip(origins.par_iter_mut())
+                .enumerate()
+                .for_each(breed);
+        } else {
+            offspring
+                .as_mut_slice()
+                .chunks_mut(cols.max(1))
+                .zip(origins.iter_mut())
+                .enumerate()
+                .for_each(breed);
+        }
+
+        let child_profits: Option<Vec<Float>> = self.profits.as_ref().map(|_| {
+            self.origins
+                .iter()
+                .map(|origin| origin.pair_profit)
+                .collect()
+        });
+        match parameters.diversity {
+            Some(diversity)
+                if diversity.method == DiversityMethod::Crowding
+                    || diversity.method == DiversityMethod::RestrictedTournament =>
+            {
+                self.replace_parents(task, &evaluation, pairing, child_profits, diversity, rng)
+            }
+            Some(diversity) if diversity.method == DiversityMethod::Deduplication => {
+                mem::swap(&mut self.population, &mut self.offspring);
+                self.profits = child_profits;
+                self.reinject_copies(task, diversity.ones_probability, rng);
+            }
+            _ => {
+                mem::swap(&mut self.population, &mut self.offspring);
+                self.profits = child_profits;
+            }
+        }
+
+        if self_adaptive {
+            self.rates
+                .iter_mut()
+                .zip(self.origins.iter())
+                .for_each(|(rates, origin)| *rates = origin.rates);
+        }
+
+        let rates = self.mean_rates().unwrap_or(Rates {
+            mutation: parameters.mutation_rate,
+            crossover: parameters.crossover_rate,
+        });
 
-            costs_vec
+        Ok(GenerationStatistics {
+            best,
+            mutation_rate: rates.mutation,
+            crossover_rate: rates.crossover,
+            success_ratio,
+            diversity: population_diversity,
+            cache_hit_rate,
         })
-        .unwrap()
     }
 
-    pub fn evolve_generation(
+    // the children in the offspring replace the parents, they compete with,
+    // chosen by the crowding or by the restricted tournament
+    fn replace_parents(
         &mut self,
         task: &Task,
-        tournament_size: usize,
-        crossover_rate: f64,
-        cutting_point: usize,
-        mutation_rate: f64,
-        workbench: &mut DMatrix<Float>,
-    ) -> DynamicResult<Float> {
-        let evaluation = self.evaluate(&task);
-
-        let (_, best) = evaluation.argmax();
-
-        workbench
-            .row_iter_mut()
-            .collect::<Vec<_>>()
-            .par_iter_mut()
-            .for_each_init(
-                || thread_rng(),
-                |mut rng, individual| {
-                    let first_parent_idx =
-                        tournament_selection(tournament_size, &evaluation, &mut rng);
-                    let second_parent_idx =
-                        tournament_selection(tournament_size, &evaluation, &mut rng);
-                    let first_parent = self.population.row(first_parent_idx);
-                    let second_parent = self.population.row(second_parent_idx);
-
-                    individual.crossover(
-                        first_parent,
-                        second_parent,
-                        crossover_rate,
-                        cutting_point,
-                        &mut rng,
-                    );
-                    individual.mutate(mutation_rate, &mut rng);
-                },
-            );
+        evaluation: &FloatDVector,
+        pairing: Option<Vec<usize>>,
+        child_profits: Option<Vec<Float>>,
+        diversity: Diversity,
+        rng: &mut Random,
+    ) {
+        // the children are evaluated as a population, while the parents wait in the offspring
+        let parent_profits = mem::replace(&mut self.profits, child_profits);
+        mem::swap(&mut self.population, &mut self.offspring);
+        let (children_evaluation, cache_hit_rate) = self.evaluate_cached(task);
+        mem::swap(&mut self.population, &mut self.offspring);
 
-        // make sure, it is safe to swap the population and workbench,
-        // return error, if they differ in size
-        if workbench.nrows() != self.population.nrows()
-            || workbench.ncols() != self.population.ncols()
-        {
-            // TODO: refactor error
-            return Err("matrices differ in size, therefore cannot be swapped".into());
+        let (rows, cols) = (self.number_of_individuals(), self.number_of_genes());
+        let Self {
+            population,
+            offspring,
+            rates,
+            origins: children_origins,
+            ..
+        } = self;
+        let distance = |parents: &PopulationMatrix, parent: usize, child: usize| {
+            diversity::hamming_distance(
+                Self::genes_of(parents, parent),
+                Self::genes_of(offspring, child),
+            )
+        };
+        // the rivals of the crowding are known in advance, each parent competes with one child
+        let rivals: Vec<usize> = match &pairing {
+            Some(order) => (0..rows)
+                .map(|child| {
+                    let other = mate(child, rows);
+                    if other == child {
+                        return order[child];
+                    }
+
+                    let straight = distance(population, order[child], child)
+                        + distance(population, order[other], other);
+                    let crossed = distance(population, order[other], child)
+                        + distance(population, order[child], other);
+                    if straight <= crossed {
+                        order[child]
+                    } else {
+                        order[other]
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut fitness = evaluation.clone();
+        // the surviving parents do not count as successes of the next generation
+        let mut origins: Vec<Origin> = (0..rows)
+            .map(|parent| Origin {
+                parent_fitness: evaluation[parent],
+                rates: rates.get(parent).copied().unwrap_or_default(),
+                pair_profit: parent_profits.as_ref().map_or(0, |profits| profits[parent]),
+            })
+            .collect();
+        let window = diversity.replacement_window.min(rows);
+
+        for child in 0..rows {
+            let rival = if pairing.is_some() {
+                rivals[child]
+            } else {
+                // the window is drawn from the population already changed by the previous children
+                index::sample(rng, rows, window)
+                    .into_iter()
+                    .min_by_key(|&parent| distance(population, parent, child))
+                    .unwrap_or(child)
+            };
+
+            if children_evaluation[child] >= fitness[rival] {
+                population.as_mut_slice()[rival * cols..(rival + 1) * cols]
+                    .copy_from_slice(Self::genes_of(offspring, child));
+                fitness[rival] = children_evaluation[child];
+                origins[rival] = children_origins[child];
+            }
         }
 
-        // we have already guaranteed, it will be safe to swap the two matrices, as they are the same size
-        // unsafe block is needed for method `as_vec_mut()` on type nalgebra::base::VecStorage
-        unsafe {
-            std::mem::swap(
-                self.population.data.as_vec_mut(),
-                workbench.data.as_vec_mut(),
-            );
+        self.profits =
+            parent_profits.map(|_| origins.iter().map(|origin| origin.pair_profit).collect());
+        self.origins = origins;
+        self.survivors = Some((fitness, cache_hit_rate));
+    }
+
+    // replaces the copies of the individuals with random ones, each genome is kept once
+    fn reinject_copies(&mut self, task: &Task, ones_probability: f64, rng: &mut Random) {
+        let cols = self.number_of_genes().max(1);
+        let genes = self.population.as_slice();
+        let hashes: Vec<u64> = if self.parallel {
+            genes.par_chunks(cols).map(genome_hash).collect()
+        } else {
+            genes.chunks(cols).map(genome_hash).collect()
         };
 
-        Ok(best)
+        // the genes are compared, so the colliding hashes do not make copies
+        let mut first_copies: HashMap<u64, usize> = HashMap::new();
+        let copies: Vec<bool> = hashes
+            .iter()
+            .enumerate()
+            .map(|(individual, &hash)| match first_copies.entry(hash) {
+                Entry::Occupied(entry) => {
+                    Self::genes_of(&self.population, *entry.get())
+                        == Self::genes_of(&self.population, individual)
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(individual);
+                    false
+                }
+            })
+            .collect();
+        if copies.iter().any(|&copy| copy) {
+            self.randomize(task, &copies, ones_probability, rng);
+        }
     }
 
-    pub fn evolve_generation_with_random_barrier(
+    /// Replaces all individuals with random ones of the given density, except for a copy of the elite.
+    pub fn restart(&mut self, task: &Task, ones_probability: f64, rng: &mut Random) {
+        self.randomize(
+            task,
+            &vec![true; self.number_of_individuals()],
+            ones_probability,
+            rng,
+        );
+
+        if let Some(elite) = &self.elite {
+            let cols = self.number_of_genes();
+            self.population.as_mut_slice()[..cols].copy_from_slice(&elite.genome);
+        }
+        // the random individuals have no parents, like the initial population
+        self.origins.clear();
+    }
+
+    /// Replaces the worst `fraction` of the individuals with random ones of the given density.
+    pub fn restart_worst(
         &mut self,
         task: &Task,
-        tournament_size: usize,
-        crossover_rate: f64,
-        mutation_rate: f64,
-        workbench: &mut DMatrix<Float>,
-    ) -> DynamicResult<Float> {
-        let evaluation = self.evaluate(&task);
-
-        let (_, best) = evaluation.argmax();
-
-        workbench
-            .row_iter_mut()
-            .collect::<Vec<_>>()
-            .par_iter_mut()
-            .for_each_init(
-                || thread_rng(),
-                |mut rng, individual| {
-                    let first_parent_idx =
-                        tournament_selection(tournament_size, &evaluation, &mut rng);
-                    let second_parent_idx =
-                        tournament_selection(tournament_size, &evaluation, &mut rng);
-                    let first_parent = self.population.row(first_parent_idx);
-                    let second_parent = self.population.row(second_parent_idx);
-
-                    individual.random_crossover(
-                        first_parent,
-                        second_parent,
-                        crossover_rate,
-                        &mut rng,
-                    );
-                    individual.mutate(mutation_rate, &mut rng);
-                },
-            );
+        fraction: f64,
+        ones_probability: f64,
+        rng: &mut Random,
+    ) {
+        let (evaluation, _) = match self.survivors.take() {
+            Some(survivors) => survivors,
+            None => self.evaluate_cached(task),
+        };
+
+        let replaced = ((fraction * self.number_of_individuals() as f64).ceil() as usize)
+            .min(self.number_of_individuals());
+        let mut order: Vec<usize> = (0..self.number_of_individuals()).collect();
+        order.sort_by_key(|&individual| evaluation[individual]);
+        let mut chosen = vec![false; self.number_of_individuals()];
+        order
+            .iter()
+            .take(replaced)
+            .for_each(|&individual| chosen[individual] = true);
+
+        self.randomize(task, &chosen, ones_probability, rng);
+    }
+
+    // replaces the chosen individuals with random, repaired ones of the given density
+    fn randomize(&mut self, task: &Task, chosen: &[bool], ones_probability: f64, rng: &mut Random) {
+        let dist = Bernoulli::new(ones_probability).expect("probability out of range [0, 1]");
+        let gene_bounds = task.get_gene_bounds();
+        self.for_each_indexed_individual(rng, |index, mut individual, rng| {
+            if !chosen[index] {
+                return;
+            }
+
+            individual
+                .iter_mut()
+                .for_each(|gene| *gene = dist.sample(rng) as Float);
+            if let Some(gene_bounds) = gene_bounds {
+                individual
+                    .iter_mut()
+                    .zip(gene_bounds.iter())
+                    .filter(|(gene, &bound)| **gene != 0 && bound > 0)
+                    .for_each(|(gene, &bound)| *gene = rng.gen_range(1, bound + 1));
+            }
+            task.get_constraints().repair(&mut individual, rng);
+            if let Some(bins) = task.get_bins() {
+                bins::repair(&mut individual, task, bins, rng);
+            }
+        });
+    }
 
-        // make sure, it is safe to swap the population and workbench,
-        // return error, if they differ in size
-        if workbench.nrows() != self.population.nrows()
-            || workbench.ncols() != self.population.ncols()
+    /// Evaluates the population, remembering its best individual, if it is the best one so far.
+    pub fn update_elite_with(&mut self, task: &Task) {
+        let (best_idx, best) = self.evaluate_cached(task).0.argmax();
+        self.update_elite(best_idx, best);
+    }
+
+    fn update_elite(&mut self, individual: usize, fitness: Float) {
+        if self
+            .elite
+            .as_ref()
+            .is_some_and(|elite| elite.fitness >= fitness)
         {
-            // TODO: refactor error
-            return Err("matrices differ in size, therefore cannot be swapped".into());
+            return;
         }
 
-        unsafe {
-            std::mem::swap(
-                self.population.data.as_vec_mut(),
-                workbench.data.as_vec_mut(),
-            );
-        };
+        self.elite = Some(Elite {
+            fitness,
+            genome: self.population.column(individual).iter().cloned().collect(),
+        });
+    }
 
-        Ok(best)
+    /// The best individual seen so far.
+    pub fn elite(&self) -> Option<&Elite> {
+        self.elite.as_ref()
     }
 
-    fn get_evaluated_vec(
+    // fraction of the individuals, which are better than the better of their parents
+    fn success_ratio(&self, evaluation: &FloatDVector) -> Option<f64> {
+        if self.origins.is_empty() {
+            return None;
+        }
+
+        let successes = evaluation
+            .iter()
+            .zip(self.origins.iter())
+            .filter(|(&fitness, origin)| fitness > origin.parent_fitness)
+            .count();
+
+        Some(successes as f64 / self.origins.len() as f64)
+    }
+
+    // each individual is a contiguous column, so its sum is a single dot product
+    fn get_summed_vec(population: &PopulationMatrix, vec: &FloatDVector) -> FloatDVector {
+        population.tr_mul(vec)
+    }
+
+    // 1 for the individuals satisfying all the constraints, 0 otherwise
+    fn get_constraints_vec(
         population: &PopulationMatrix,
-        vec: &FloatDVector,
-        max_sum: usize,
+        constraints: &Constraints,
     ) -> FloatDVector {
-        let mut summed_vec = Self::get_summed_vec(&population, vec);
-        let data_vec = unsafe { summed_vec.data.as_vec_mut() };
-        data_vec.into_par_iter().for_each(|elem| {
-            // println!("{} | {} :: {}", &elem, &max_sum, (*elem > max_sum as Float));
-            *elem = (*elem <= max_sum as Float) as u8 as Float
+        FloatDVector::from_fn(population.ncols(), |individual, _| {
+            let violations = constraints.violations(|item| population[(item, individual)] != 0);
+            (violations.total() == 0) as u8 as Float
+        })
+    }
+
+    /// Repairs all individuals, so they satisfy the constraints of the task, see `Constraints::repair`.
+    pub fn repair(&mut self, constraints: &Constraints, rng: &mut Random) {
+        if constraints.is_empty() {
+            return;
+        }
+
+        self.for_each_individual(rng, |mut individual, rng| {
+            constraints.repair(&mut individual, rng)
         });
+    }
 
-        summed_vec
+    /// Numbers of the violated constraints of each individual.
+    pub fn violations(&self, constraints: &Constraints) -> Vec<Violations> {
+        self.population
+            .column_iter()
+            .map(|genes| constraints.violations(|item| genes[item] != 0))
+            .collect()
     }
 
-    fn get_summed_vec(population: &PopulationMatrix, vec: &FloatDVector) -> FloatDVector {
-        population * vec
+    /// Sums of the weights, sizes and costs of the items selected by each individual,
+    /// the costs include the profits of the pairs of items.
+    pub fn resource_sums(&self, task: &Task) -> (FloatDVector, FloatDVector, FloatDVector) {
+        let sums = self.individual_sums(task);
+        let vec = |value: fn(&(Float, Float, Float)) -> Float| {
+            FloatDVector::from_iterator(sums.len(), sums.iter().map(value))
+        };
+
+        (vec(|sums| sums.0), vec(|sums| sums.1), vec(|sums| sums.2))
     }
 
-    pub fn rows(&self) -> usize {
-        self.population.nrows()
+    /// Sums of the secondary costs of the items selected by each individual,
+    /// if the task has the secondary costs.
+    pub fn secondary_cost_sums(&self, task: &Task) -> Option<FloatDVector> {
+        task.get_secondary_costs()
+            .map(|costs| Self::get_summed_vec(&self.population, costs))
     }
 
-    pub fn cols(&self) -> usize {
+    /// Replaces all individuals with the offspring of `parents`,
+    /// which are chosen with the `select` function.
+    pub fn breed_from<S>(
+        &mut self,
+        parents: &Population,
+        select: S,
+        crossover_rate: f64,
+        mutation_rate: f64,
+        constraints: &Constraints,
+        rng: &mut Random,
+    ) where
+        S: Fn(&mut Random) -> usize + Sync + Send,
+    {
+        let parents = &parents.population;
+
+        self.for_each_individual(rng, |mut individual, rng| {
+            let first_parent = Self::individual_of(parents, select(rng));
+            let second_parent = Self::individual_of(parents, select(rng));
+
+            individual.random_crossover(first_parent, second_parent, crossover_rate, rng);
+            individual.mutate(mutation_rate, rng);
+            constraints.repair(&mut individual, rng);
+        });
+    }
+
+    /// Creates a population from the chosen individuals of `first` and `second`.
+    /// Indices lower than `first.number_of_individuals()` refer to `first`, the rest to `second`.
+    pub fn select_from(first: &Population, second: &Population, indices: &[usize]) -> Self {
+        let matrix = PopulationMatrix::from_fn(
+            first.number_of_genes(),
+            indices.len(),
+            |gene, individual| {
+                let index = indices[individual];
+                if index < first.number_of_individuals() {
+                    first.population[(gene, index)]
+                } else {
+                    second.population[(gene, index - first.number_of_individuals())]
+                }
+            },
+        );
+
+        let mut selected = Self::from(matrix);
+        selected.parallel = first.parallel;
+        selected.evaluation = first.evaluation;
+        selected
+    }
+
+    /// Indices of the items selected by the individual.
+    pub fn selected_items(&self, individual: usize) -> Vec<usize> {
+        self.population
+            .column(individual)
+            .iter()
+            .enumerate()
+            .filter(|(_, &gene)| gene != 0)
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    /// Number of the individuals.
+    pub fn number_of_individuals(&self) -> usize {
         self.population.ncols()
     }
-}
 
-pub fn tournament_selection(
-    tournament_size: usize,
-    evaluation: &FloatDVector,
-    rng: &mut ThreadRng,
-) -> usize {
-    let mut sparse_tournament = get_sparse_DVec_with(evaluation.nrows(), tournament_size, rng);
-    sparse_tournament.component_mul_assign(evaluation);
+    /// Number of the genes of each individual.
+    pub fn number_of_genes(&self) -> usize {
+        self.population.nrows()
+    }
+
+    /// The genes of the individual.
+    pub fn individual(&self, individual: usize) -> Individual<'_> {
+        Self::individual_of(&self.population, individual)
+    }
 
-    let (winner_pos, _) = sparse_tournament.argmax();
-    // dbg!(winner_pos, winner_score);
+    /// The individuals, which can be changed in parallel.
+    pub fn individuals_mut(&mut self) -> impl IndexedParallelIterator<Item = IndividualMut<'_>> {
+        self.survivors = None;
+        self.profits = None;
+        let cols = self.number_of_genes();
+        self.population
+            .as_mut_slice()
+            .par_chunks_mut(cols.max(1))
+            .map(as_individual_mut)
+    }
 
-    winner_pos
+    fn individual_of(population: &PopulationMatrix, individual: usize) -> Individual<'_> {
+        as_individual(Self::genes_of(population, individual))
+    }
+
+    fn genes_of(population: &PopulationMatrix, individual: usize) -> &[Float] {
+        let cols = population.nrows();
+        &population.as_slice()[individual * cols..(individual + 1) * cols]
+    }
+}
+
+// the other parent of the child bred by the crowding, pairs of the neighbouring children share
+// their parents, the last child of an odd population is bred from a single parent
+fn mate(child: usize, rows: usize) -> usize {
+    if child % 2 == 1 {
+        child - 1
+    } else if child + 1 < rows {
+        child + 1
+    } else {
+        child
+    }
 }
 
 impl From<PopulationMatrix> for Population {
     fn from(matrix: PopulationMatrix) -> Self {
-        Self { population: matrix }
+        Self {
+            population: matrix,
+            rates: Vec::new(),
+            origins: Vec::new(),
+            elite: None,
+            offspring: PopulationMatrix::zeros(0, 0),
+            parallel: true,
+            evaluation: EvaluationBackend::Auto,
+            cache: None,
+            survivors: None,
+            profits: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::selection::TieBreak;
+    use crate::fixtures::{random_interactions, random_task};
+    use rand::SeedableRng;
+
+    fn interacting_task(items: usize, pairs: usize, rng: &mut Random) -> Task {
+        random_task(items, 1..100, 1.0 / 3.0, rng)
+            .with_interactions(random_interactions(items, pairs, rng))
+    }
+
+    fn parameters(diversity: Option<Diversity>) -> GenerationParameters<'static> {
+        GenerationParameters {
+            tournament: Tournament {
+                size: 3,
+                with_replacement: false,
+                tie_break: TieBreak::LowestIndex,
+            },
+            crossover_rate: 0.5,
+            cutting_point: None,
+            mutation_rate: 0.02,
+            local_search: None,
+            local_search_rate: 0.0,
+            self_adaptive: false,
+            integer_mutation: IntegerMutation::Step,
+            integer_crossover: IntegerCrossover::OnePoint,
+            diversity,
+            population_diversity: None,
+        }
+    }
+
+    fn diversity(method: DiversityMethod) -> Diversity {
+        Diversity {
+            method,
+            sharing_radius: 0.2,
+            replacement_window: 5,
+            ones_probability: 0.3,
+        }
+    }
+
+    #[test]
+    fn derived_pair_profits_equal_summed_ones() {
+        let mut rng = Random::seed_from_u64(39);
+        let task = interacting_task(80, 300, &mut rng);
+        let methods = [
+            None,
+            Some(DiversityMethod::Deduplication),
+            Some(DiversityMethod::Crowding),
+            Some(DiversityMethod::RestrictedTournament),
+        ];
+
+        for &method in methods.iter() {
+            let parameters = parameters(method.map(diversity));
+            let mut population = Population::generate_initial_population(30, 80, 0.3, &mut rng);
+            population.enable_fitness_cache(16);
+
+            for _ in 0..10 {
+                population
+                    .evolve_generation(&task, &parameters, &mut rng)
+                    .unwrap();
+
+                let summed = Population::from(population.population.clone());
+                if let Some(profits) = &population.profits {
+                    assert_eq!(profits, &summed.pair_profits(&task));
+                }
+                assert_eq!(population.evaluate(&task), summed.evaluate(&task));
+            }
+        }
+    }
+
+    #[test]
+    fn cache_hits_repeated_genomes() {
+        let mut rng = Random::seed_from_u64(48);
+        let task = interacting_task(50, 40, &mut rng);
+        // the second half of the individuals copies the first one
+        let genomes = PopulationMatrix::from_fn(50, 20, |_, _| rng.gen_range(0, 2));
+        let genomes =
+            PopulationMatrix::from_fn(50, 40, |item, individual| genomes[(item, individual % 20)]);
+        let mut population = Population::from(genomes);
+        let expected = population.evaluate(&task);
+
+        let (evaluation, hit_rate) = population.evaluate_cached(&task);
+        assert_eq!(hit_rate, None);
+        assert_eq!(evaluation, expected);
+
+        // the copies of a genome missing from the cache count as hits, as they are evaluated once
+        population.enable_fitness_cache(1 << 16);
+        let (evaluation, hit_rate) = population.evaluate_cached(&task);
+        assert_eq!(evaluation, expected);
+        assert_eq!(hit_rate, Some(0.5));
+
+        let (evaluation, hit_rate) = population.evaluate_cached(&task);
+        assert_eq!(evaluation, expected);
+        assert_eq!(hit_rate, Some(1f64));
+
+        // a single slot keeps only the last genome, the other 19 ones are evicted
+        population.enable_fitness_cache(1);
+        population.evaluate_cached(&task);
+        let (evaluation, hit_rate) = population.evaluate_cached(&task);
+        assert_eq!(evaluation, expected);
+        assert_eq!(hit_rate, Some(1f64 - 19f64 / 40f64));
+    }
+
+    fn distinct_genomes(population: &Population) -> usize {
+        let cols = population.number_of_genes();
+        let mut genomes: Vec<&[Float]> = population.population.as_slice().chunks(cols).collect();
+        genomes.sort_unstable();
+        genomes.dedup();
+        genomes.len()
+    }
+
+    #[test]
+    fn deduplication_keeps_each_genome_once() {
+        let mut rng = Random::seed_from_u64(49);
+        let task = interacting_task(60, 0, &mut rng);
+        let genome = PopulationMatrix::from_fn(60, 1, |_, _| rng.gen_range(0, 2));
+        // the children of the copies are copies too, as nothing is mutated
+        let mut population = Population::from(PopulationMatrix::from_fn(60, 20, |item, _| {
+            genome[(item, 0)]
+        }));
+        let mut parameters = parameters(Some(diversity(DiversityMethod::Deduplication)));
+        parameters.mutation_rate = 0f64;
+
+        population
+            .evolve_generation(&task, &parameters, &mut rng)
+            .unwrap();
+        assert_eq!(distinct_genomes(&population), 20);
+        assert!(population
+            .population
+            .as_slice()
+            .chunks(60)
+            .any(|genes| genes == genome.as_slice()));
+    }
+
+    #[test]
+    fn sharing_selects_by_shared_fitness() {
+        let mut rng = Random::seed_from_u64(49);
+        let task = interacting_task(60, 0, &mut rng);
+        let mut population = Population::generate_initial_population(20, 60, 0.2, &mut rng);
+        let parameters = parameters(Some(diversity(DiversityMethod::Sharing)));
+
+        // the raw fitness is reported, the shared one is used only by the selection
+        let expected = population.evaluate(&task).max();
+        let statistics = population
+            .evolve_generation(&task, &parameters, &mut rng)
+            .unwrap();
+        assert_eq!(statistics.best, expected);
+    }
+
+    #[test]
+    fn replaced_parents_are_never_better() {
+        let mut rng = Random::seed_from_u64(49);
+        let task = interacting_task(60, 100, &mut rng);
+
+        for &method in &[
+            DiversityMethod::Crowding,
+            DiversityMethod::RestrictedTournament,
+        ] {
+            let parameters = parameters(Some(diversity(method)));
+            let mut population = Population::generate_initial_population(21, 60, 0.2, &mut rng);
+
+            for _ in 0..10 {
+                let before = population.evaluate(&task);
+                population
+                    .evolve_generation(&task, &parameters, &mut rng)
+                    .unwrap();
+
+                // each child replaces a parent only if it is at least as good
+                let after = Population::from(population.population.clone()).evaluate(&task);
+                assert!(before.iter().zip(after.iter()).all(|(b, a)| a >= b));
+                assert_eq!(
+                    population.survivors.as_ref().map(|(fitness, _)| fitness),
+                    Some(&after)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn restart_keeps_elite() {
+        let mut rng = Random::seed_from_u64(50);
+        let task = interacting_task(60, 50, &mut rng);
+        let mut population = Population::generate_initial_population(20, 60, 0.2, &mut rng);
+        let parameters = parameters(None);
+        for _ in 0..5 {
+            population
+                .evolve_generation(&task, &parameters, &mut rng)
+                .unwrap();
+        }
+        let elite = population.elite().unwrap().clone();
+
+        population.restart(&task, 0.2, &mut rng);
+        assert_eq!(
+            Population::genes_of(&population.population, 0),
+            elite.genome.as_slice()
+        );
+        assert_eq!(population.evaluate(&task)[0], elite.fitness);
+        assert!(distinct_genomes(&population) > 15);
+
+        // the elite survives the next generation as well
+        let statistics = population
+            .evolve_generation(&task, &parameters, &mut rng)
+            .unwrap();
+        assert_eq!(statistics.best, elite.fitness);
+        assert_eq!(population.elite().unwrap().fitness, elite.fitness);
+    }
+
+    #[test]
+    fn partial_restart_replaces_worst_individuals() {
+        let mut rng = Random::seed_from_u64(50);
+        let task = interacting_task(60, 50, &mut rng);
+        let mut population = Population::generate_initial_population(20, 60, 0.2, &mut rng);
+        let before = population.population.clone();
+        let evaluation = population.evaluate(&task);
+        let mut order: Vec<usize> = (0..20).collect();
+        order.sort_by_key(|&individual| evaluation[individual]);
+
+        population.restart_worst(&task, 0.25, 0.2, &mut rng);
+        let changed = |individual: usize| {
+            Population::genes_of(&population.population, individual)
+                != Population::genes_of(&before, individual)
+        };
+        // the five worst individuals are replaced, the others are kept
+        assert!(order[5..].iter().all(|&individual| !changed(individual)));
+        assert!(
+            order[..5]
+                .iter()
+                .filter(|&&individual| changed(individual))
+                .count()
+                >= 4
+        );
+    }
+
+    #[test]
+    fn inherited_rates_are_clamped() {
+        let mut rng = Random::seed_from_u64(30);
+        let extreme = |mutation, crossover| Rates {
+            mutation,
+            crossover,
+        };
+
+        for _ in 0..1000 {
+            let high = Rates::inherit(extreme(0.5, 1.0), extreme(0.5, 1.0), 0.01, &mut rng);
+            let low = Rates::inherit(extreme(0.01, 0.0), extreme(0.01, 0.0), 0.01, &mut rng);
+            for rates in [high, low].iter() {
+                assert!((0.01..=MAX_RATE).contains(&rates.mutation));
+                assert!((0f64..=1f64).contains(&rates.crossover));
+            }
+        }
+
+        // the rates of the parents at the bounds are kept within them by the perturbation
+        let hit = |value: f64, bound: f64| (value - bound).abs() < 1e-12;
+        let rates: Vec<Rates> = (0..200)
+            .map(|_| Rates::inherit(extreme(0.5, 1.0), extreme(0.5, 1.0), 0.01, &mut rng))
+            .collect();
+        assert!(rates.iter().any(|rates| hit(rates.mutation, MAX_RATE)));
+        assert!(rates.iter().any(|rates| hit(rates.crossover, 1f64)));
     }
 }
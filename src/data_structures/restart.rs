@@ -0,0 +1,311 @@
+use super::{schedule::MAX_RATE, DynamicResult, Float};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the population is changed, when the search stagnates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// all individuals are replaced with random ones, except for a copy of the elite
+    Full,
+    /// the worst `restart-fraction` of the individuals is replaced with random ones
+    Partial,
+    /// the next `hypermutation-generations` generations are bred with the `hypermutation-rate`
+    Hypermutation,
+}
+
+impl FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(RestartPolicy::Full),
+            "partial" => Ok(RestartPolicy::Partial),
+            "hypermutation" => Ok(RestartPolicy::Hypermutation),
+            _ => Err(format!(
+                "unknown restart policy: {}, expected one of: full, partial, hypermutation",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RestartPolicy::Full => "full",
+            RestartPolicy::Partial => "partial",
+            RestartPolicy::Hypermutation => "hypermutation",
+        })
+    }
+}
+
+// Options of the restarts of the stagnating search.
+#[derive(StructOpt, Debug, Clone, Default)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RestartOptions {
+    /// policy applied, when the search stagnates, none by default.
+    /// One of: full, partial, hypermutation
+    #[structopt(long)]
+    pub restart: Option<RestartPolicy>,
+
+    /// number of the generations without an improvement of the best fitness,
+    /// after which the population is restarted
+    #[structopt(long)]
+    pub restart_stagnation: Option<usize>,
+
+    /// normalized Hamming diversity of the population, below which it is restarted
+    #[structopt(long)]
+    pub restart_diversity: Option<f64>,
+
+    /// fraction of the worst individuals replaced by the partial restart,
+    /// should be set in range (0, 1]
+    #[structopt(long, default_value = "0.5")]
+    pub restart_fraction: f64,
+
+    /// mutation rate of the generations bred after the hypermutation restart
+    #[structopt(long, default_value = "0.1")]
+    pub hypermutation_rate: f64,
+
+    /// number of the generations bred with the `hypermutation-rate`
+    #[structopt(long, default_value = "5")]
+    pub hypermutation_generations: usize,
+}
+
+impl RestartOptions {
+    /// Decides, when the population is restarted, if any policy is chosen.
+    pub fn restarts(&self) -> DynamicResult<Option<Restarts>> {
+        let policy = match self.restart {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+
+        if self.restart_stagnation.is_none() && self.restart_diversity.is_none() {
+            return Err("restart needs restart-stagnation or restart-diversity to be set".into());
+        }
+        if self.restart_stagnation == Some(0) {
+            return Err("restart stagnation has to be a positive, non zero integer".into());
+        }
+        if self.restart_fraction <= 0f64 || self.restart_fraction > 1f64 {
+            return Err("restart fraction has to be in range (0, 1]".into());
+        }
+        if self.hypermutation_rate <= 0f64 || self.hypermutation_rate > MAX_RATE {
+            return Err(format!("hypermutation rate has to be in range (0, {}]", MAX_RATE).into());
+        }
+
+        Ok(Some(Restarts {
+            policy,
+            stagnation: self.restart_stagnation,
+            diversity: self.restart_diversity,
+            fraction: self.restart_fraction,
+            hypermutation_rate: self.hypermutation_rate,
+            hypermutation_generations: self.hypermutation_generations,
+            best: None,
+            stagnant_generations: 0,
+            hypermutated_generations: 0,
+            skipped_checks: 0,
+        }))
+    }
+}
+
+/// Why the population has been restarted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartReason {
+    /// the best fitness has not improved for this many generations
+    Stagnation(usize),
+    /// the diversity of the population has fallen to this value
+    Diversity(f64),
+}
+
+/// A single restart of the population.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartEvent {
+    pub generation: usize,
+    pub policy: RestartPolicy,
+    pub reason: RestartReason,
+}
+
+impl fmt::Display for RestartEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} restart after generation {}, ",
+            self.policy, self.generation
+        )?;
+        match self.reason {
+            RestartReason::Stagnation(generations) => write!(
+                f,
+                "the best fitness has not improved for {} generations",
+                generations
+            ),
+            RestartReason::Diversity(diversity) => {
+                write!(f, "the diversity has fallen to {:.4}", diversity)
+            }
+        }
+    }
+}
+
+/// Watches the generations and decides, when the population is restarted.
+#[derive(Debug, Clone)]
+pub struct Restarts {
+    policy: RestartPolicy,
+    stagnation: Option<usize>,
+    diversity: Option<f64>,
+    fraction: f64,
+    hypermutation_rate: f64,
+    hypermutation_generations: usize,
+    // the best fitness seen so far
+    best: Option<Float>,
+    stagnant_generations: usize,
+    // generations left to breed with the hypermutation rate
+    hypermutated_generations: usize,
+    // checks, which do not trigger any restart, as the children of the generations
+    // bred with the hypermutation rate are evaluated in the next generation
+    skipped_checks: usize,
+}
+
+impl Restarts {
+    /// Fraction of the worst individuals replaced by the partial restart.
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Returns the restart triggered by the evaluated generation, if any.
+    /// The generations bred with the hypermutation rate do not trigger any restart.
+    pub fn check(
+        &mut self,
+        generation: usize,
+        best: Float,
+        diversity: f64,
+    ) -> Option<RestartEvent> {
+        if self.best.is_some_and(|seen| seen >= best) {
+            self.stagnant_generations += 1;
+        } else {
+            self.best = Some(best);
+            self.stagnant_generations = 0;
+        }
+
+        if self.skipped_checks > 0 {
+            self.skipped_checks -= 1;
+            return None;
+        }
+
+        let reason = match (self.stagnation, self.diversity) {
+            (Some(stagnation), _) if self.stagnant_generations >= stagnation => {
+                RestartReason::Stagnation(self.stagnant_generations)
+            }
+            (_, Some(threshold)) if diversity < threshold => RestartReason::Diversity(diversity),
+            _ => return None,
+        };
+
+        self.stagnant_generations = 0;
+        if self.policy == RestartPolicy::Hypermutation {
+            self.hypermutated_generations = self.hypermutation_generations;
+            self.skipped_checks = self.hypermutation_generations + 1;
+        }
+
+        Some(RestartEvent {
+            generation,
+            policy: self.policy,
+            reason,
+        })
+    }
+
+    /// The hypermutation rate, if the next generation is bred after a hypermutation restart.
+    pub fn hypermutation(&mut self) -> Option<f64> {
+        if self.hypermutated_generations == 0 {
+            return None;
+        }
+
+        self.hypermutated_generations -= 1;
+        Some(self.hypermutation_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn restarts(policy: &str, stagnation: Option<usize>, diversity: Option<f64>) -> Restarts {
+        RestartOptions {
+            restart: Some(policy.parse().unwrap()),
+            restart_stagnation: stagnation,
+            restart_diversity: diversity,
+            restart_fraction: 0.5,
+            hypermutation_rate: 0.1,
+            hypermutation_generations: 2,
+        }
+        .restarts()
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn options_are_validated() {
+        let options = |stagnation, fraction| RestartOptions {
+            restart: Some(RestartPolicy::Partial),
+            restart_stagnation: stagnation,
+            restart_diversity: None,
+            restart_fraction: fraction,
+            hypermutation_rate: 0.1,
+            hypermutation_generations: 5,
+        };
+
+        assert!(RestartOptions::default().restarts().unwrap().is_none());
+        assert!(options(Some(3), 0.5).restarts().is_ok());
+        assert!(options(None, 0.5).restarts().is_err());
+        assert!(options(Some(0), 0.5).restarts().is_err());
+        assert!(options(Some(3), 0f64).restarts().is_err());
+    }
+
+    #[test]
+    fn stagnation_triggers_restart() {
+        let mut restarts = restarts("full", Some(3), None);
+
+        // improvements reset the count of the stagnant generations
+        assert!(restarts.check(0, 10, 0.5).is_none());
+        assert!(restarts.check(1, 10, 0.5).is_none());
+        assert!(restarts.check(2, 12, 0.5).is_none());
+        assert!(restarts.check(3, 12, 0.5).is_none());
+        assert!(restarts.check(4, 11, 0.5).is_none());
+
+        let event = restarts.check(5, 12, 0.5).unwrap();
+        assert_eq!(event.generation, 5);
+        assert_eq!(event.policy, RestartPolicy::Full);
+        assert_eq!(event.reason, RestartReason::Stagnation(3));
+
+        // the count starts again after the restart, and the best fitness is kept
+        assert!(restarts.check(6, 12, 0.5).is_none());
+        assert!(restarts.check(7, 12, 0.5).is_none());
+        assert!(restarts.check(8, 12, 0.5).is_some());
+    }
+
+    #[test]
+    fn low_diversity_triggers_restart() {
+        let mut restarts = restarts("partial", None, Some(0.1));
+
+        assert!(restarts.check(0, 10, 0.3).is_none());
+        let event = restarts.check(1, 11, 0.05).unwrap();
+        assert_eq!(event.reason, RestartReason::Diversity(0.05));
+        assert_eq!(restarts.fraction(), 0.5);
+    }
+
+    #[test]
+    fn hypermutation_lasts_given_generations() {
+        let mut restarts = restarts("hypermutation", Some(1), None);
+        assert!(restarts.hypermutation().is_none());
+
+        assert!(restarts.check(0, 10, 0.5).is_none());
+        assert!(restarts.check(1, 10, 0.5).is_some());
+
+        // the hypermutated generations, evaluated in the next generation,
+        // do not trigger another restart
+        assert_eq!(restarts.hypermutation(), Some(0.1));
+        assert!(restarts.check(2, 10, 0.5).is_none());
+        assert_eq!(restarts.hypermutation(), Some(0.1));
+        assert!(restarts.check(3, 10, 0.5).is_none());
+        assert!(restarts.hypermutation().is_none());
+        assert!(restarts.check(4, 10, 0.5).is_none());
+        assert!(restarts.hypermutation().is_none());
+        assert!(restarts.check(5, 10, 0.5).is_some());
+    }
+}
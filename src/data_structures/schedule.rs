@@ -0,0 +1,206 @@
+use std::str::FromStr;
+
+// multiplier used by the 1/5th success rule
+const ONE_FIFTH_FACTOR: f64 = 0.85;
+
+/// no schedule mutates more than half of the genes
+pub const MAX_RATE: f64 = 0.5;
+
+// the diversity driven schedule does not raise the mutation rate more than this many times
+const MAX_DIVERSITY_BOOST: f64 = 10f64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateSchedule {
+    /// the mutation rate does not change during the run
+    Constant,
+    /// the mutation rate decreases linearly to `final-mutation-rate`
+    Linear,
+    /// the mutation rate decreases exponentially to `final-mutation-rate`
+    Exponential,
+    /// the mutation rate is raised, when the diversity of the population falls below the threshold
+    Diversity,
+    /// the mutation rate is adapted with the 1/5th success rule
+    OneFifth,
+    /// each individual carries its own mutation and crossover rates, which evolve with it
+    SelfAdaptive,
+}
+
+impl FromStr for RateSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(RateSchedule::Constant),
+            "linear" => Ok(RateSchedule::Linear),
+            "exponential" => Ok(RateSchedule::Exponential),
+            "diversity" => Ok(RateSchedule::Diversity),
+            "one-fifth" => Ok(RateSchedule::OneFifth),
+            "self-adaptive" => Ok(RateSchedule::SelfAdaptive),
+            _ => Err(format!(
+                "unknown rate schedule: {}, expected one of: constant, linear, exponential, \
+                 diversity, one-fifth, self-adaptive",
+                s
+            )),
+        }
+    }
+}
+
+/// Computes the mutation rate used in each generation.
+#[derive(Debug, Clone)]
+pub struct MutationSchedule {
+    schedule: RateSchedule,
+    initial_rate: f64,
+    final_rate: f64,
+    iterations: usize,
+    diversity_threshold: f64,
+    // the rate adapted by the 1/5th success rule
+    current_rate: f64,
+    min_rate: f64,
+    max_rate: f64,
+}
+
+impl MutationSchedule {
+    pub fn new(
+        schedule: RateSchedule,
+        initial_rate: f64,
+        final_rate: f64,
+        iterations: usize,
+        diversity_threshold: f64,
+        number_of_genes: usize,
+    ) -> Self {
+        Self {
+            schedule,
+            initial_rate,
+            final_rate,
+            iterations,
+            diversity_threshold,
+            current_rate: initial_rate,
+            // with lower rate, no gene would be mutated at all
+            min_rate: (1f64 / number_of_genes.max(1) as f64).min(MAX_RATE),
+            max_rate: MAX_RATE,
+        }
+    }
+
+    pub fn schedule(&self) -> RateSchedule {
+        self.schedule
+    }
+
+    /// Mutation rate in the given generation.
+    ///
+    /// `diversity` is used only by the diversity driven schedule.
+    pub fn rate(&self, generation: usize, diversity: Option<f64>) -> f64 {
+        let progress = if self.iterations > 1 {
+            generation as f64 / (self.iterations - 1) as f64
+        } else {
+            0f64
+        };
+
+        match self.schedule {
+            RateSchedule::Constant | RateSchedule::SelfAdaptive => self.initial_rate,
+            RateSchedule::Linear => {
+                self.initial_rate + (self.final_rate - self.initial_rate) * progress
+            }
+            RateSchedule::Exponential => {
+                if self.initial_rate > 0f64 && self.final_rate > 0f64 {
+                    self.initial_rate * (self.final_rate / self.initial_rate).powf(progress)
+                } else {
+                    self.initial_rate
+                }
+            }
+            RateSchedule::Diversity => match diversity {
+                Some(diversity) if diversity < self.diversity_threshold => {
+                    let boost = if diversity > 0f64 {
+                        (self.diversity_threshold / diversity).min(MAX_DIVERSITY_BOOST)
+                    } else {
+                        MAX_DIVERSITY_BOOST
+                    };
+                    (self.initial_rate * boost).min(self.max_rate)
+                }
+                _ => self.initial_rate,
+            },
+            RateSchedule::OneFifth => self.current_rate,
+        }
+    }
+
+    /// Updates the 1/5th success rule with the fraction of the offspring,
+    /// that turned out better than their parents.
+    pub fn update(&mut self, success_ratio: Option<f64>) {
+        if self.schedule != RateSchedule::OneFifth {
+            return;
+        }
+
+        if let Some(success_ratio) = success_ratio {
+            if success_ratio > 0.2 {
+                self.current_rate /= ONE_FIFTH_FACTOR;
+            } else if success_ratio < 0.2 {
+                self.current_rate *= ONE_FIFTH_FACTOR;
+            }
+            self.current_rate = self.current_rate.clamp(self.min_rate, self.max_rate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITERATIONS: usize = 101;
+
+    fn schedule(schedule: RateSchedule) -> MutationSchedule {
+        MutationSchedule::new(schedule, 0.1, 0.001, ITERATIONS, 0.2, 100)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-12,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn linear_and_exponential_rates_at_start_middle_and_end() {
+        let linear = schedule(RateSchedule::Linear);
+        assert_close(linear.rate(0, None), 0.1);
+        assert_close(linear.rate(50, None), (0.1 + 0.001) / 2f64);
+        assert_close(linear.rate(ITERATIONS - 1, None), 0.001);
+
+        let exponential = schedule(RateSchedule::Exponential);
+        assert_close(exponential.rate(0, None), 0.1);
+        assert_close(exponential.rate(50, None), (0.1f64 * 0.001).sqrt());
+        assert_close(exponential.rate(ITERATIONS - 1, None), 0.001);
+
+        let constant = schedule(RateSchedule::Constant);
+        for &generation in &[0, 50, ITERATIONS - 1] {
+            assert_close(constant.rate(generation, None), 0.1);
+        }
+    }
+
+    #[test]
+    fn diversity_raises_the_rate_below_the_threshold() {
+        let diversity = schedule(RateSchedule::Diversity);
+        assert_close(diversity.rate(0, Some(0.5)), 0.1);
+        assert_close(diversity.rate(0, Some(0.1)), 0.2);
+        // the boost is limited, and so is the rate
+        assert_close(diversity.rate(0, Some(0.001)), MAX_RATE);
+        assert_close(diversity.rate(0, None), 0.1);
+    }
+
+    #[test]
+    fn one_fifth_rule_stays_within_the_bounds() {
+        let mut one_fifth = schedule(RateSchedule::OneFifth);
+        one_fifth.update(Some(0.5));
+        assert_close(one_fifth.rate(1, None), 0.1 / ONE_FIFTH_FACTOR);
+
+        for _ in 0..100 {
+            one_fifth.update(Some(1.0));
+        }
+        assert_close(one_fifth.rate(2, None), MAX_RATE);
+        for _ in 0..100 {
+            one_fifth.update(Some(0.0));
+        }
+        // a single gene of the hundred ones is mutated at least
+        assert_close(one_fifth.rate(3, None), 0.01);
+    }
+}
@@ -0,0 +1,209 @@
+use super::{utils::sample_without_replacement_into, DynamicResult, Float, FloatDVector, Random};
+use rand::Rng;
+use std::{cell::RefCell, str::FromStr};
+
+thread_local! {
+    // the contestants drawn without replacement and the contestants tied for the win,
+    // kept between the tournaments of a thread, so a selection does not allocate
+    static BUFFERS: RefCell<(Vec<usize>, Vec<usize>)> = RefCell::new(Default::default());
+}
+
+/// How the winner is chosen out of the contestants with the same, best fitness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreak {
+    /// the contestant with the lowest index in the population wins
+    LowestIndex,
+    /// each of the tied contestants wins with the same probability
+    Random,
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lowest-index" => Ok(TieBreak::LowestIndex),
+            "random" => Ok(TieBreak::Random),
+            _ => Err(format!(
+                "unknown tie break: {}, expected one of: lowest-index, random",
+                s
+            )),
+        }
+    }
+}
+
+/// Tournament selection, which compares only the drawn contestants,
+/// so a single selection takes `size` steps, regardless of the population size.
+#[derive(Debug, Clone, Copy)]
+pub struct Tournament {
+    pub size: usize,
+    /// if set, the same individual can be drawn more than once
+    pub with_replacement: bool,
+    pub tie_break: TieBreak,
+}
+
+impl Tournament {
+    /// Checks, that the tournament has contestants,
+    /// and that there are enough individuals to draw them without replacement.
+    pub fn validate(&self, population_size: usize) -> DynamicResult<()> {
+        if self.size == 0 {
+            return Err("tournament size has to be positive".into());
+        }
+        if !self.with_replacement && self.size > population_size {
+            return Err(format!(
+                "tournament size {} is bigger than the population size {}, \
+                 the contestants can be drawn only with replacement",
+                self.size, population_size
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Index of the winner of a tournament among the individuals evaluated by `evaluation`.
+    pub fn select(&self, evaluation: &FloatDVector, rng: &mut Random) -> usize {
+        let population_size = evaluation.nrows();
+
+        BUFFERS.with(|buffers| {
+            let (contestants, tied) = &mut *buffers.borrow_mut();
+            if self.with_replacement {
+                self.winner(
+                    |_, rng| rng.gen_range(0, population_size),
+                    evaluation,
+                    tied,
+                    rng,
+                )
+            } else {
+                sample_without_replacement_into(population_size, self.size, rng, contestants);
+                self.winner(|i, _| contestants[i], evaluation, tied, rng)
+            }
+        })
+    }
+
+    // `contestant` gives the i-th contestant of the tournament, `tied` collects the different
+    // contestants with the best fitness, so one drawn repeatedly is not favoured by the tie break
+    fn winner<C>(
+        &self,
+        mut contestant: C,
+        evaluation: &FloatDVector,
+        tied: &mut Vec<usize>,
+        rng: &mut Random,
+    ) -> usize
+    where
+        C: FnMut(usize, &mut Random) -> usize,
+    {
+        let mut best_fitness: Option<Float> = None;
+        tied.clear();
+
+        for i in 0..self.size {
+            let contestant = contestant(i, rng);
+            let fitness = evaluation[contestant];
+            match best_fitness {
+                Some(best) if fitness < best => {}
+                Some(best) if fitness == best => {
+                    if !tied.contains(&contestant) {
+                        tied.push(contestant);
+                    }
+                }
+                _ => {
+                    best_fitness = Some(fitness);
+                    tied.clear();
+                    tied.push(contestant);
+                }
+            }
+        }
+
+        match (tied.len(), self.tie_break) {
+            (0, _) => panic!("tournament without contestants"),
+            (1, _) => tied[0],
+            (_, TieBreak::LowestIndex) => *tied.iter().min().unwrap(),
+            (ties, TieBreak::Random) => tied[rng.gen_range(0, ties)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn tournament(size: usize, with_replacement: bool, tie_break: TieBreak) -> Tournament {
+        Tournament {
+            size,
+            with_replacement,
+            tie_break,
+        }
+    }
+
+    #[test]
+    fn lowest_index_wins_the_tie() {
+        let evaluation = FloatDVector::from_vec(vec![3, 7, 1, 7, 7, 2]);
+        let mut rng = Random::seed_from_u64(44);
+
+        // all individuals compete, so the first of the best ones always wins
+        let selection = tournament(6, false, TieBreak::LowestIndex);
+        for _ in 0..100 {
+            assert_eq!(selection.select(&evaluation, &mut rng), 1);
+        }
+    }
+
+    #[test]
+    fn random_tie_break_picks_each_tied_contestant() {
+        let evaluation = FloatDVector::from_vec(vec![3, 7, 1, 7, 7, 2]);
+        let mut rng = Random::seed_from_u64(44);
+        let selection = tournament(6, false, TieBreak::Random);
+
+        let mut wins = [0usize; 6];
+        for _ in 0..3000 {
+            wins[selection.select(&evaluation, &mut rng)] += 1;
+        }
+        assert_eq!(wins[0] + wins[2] + wins[5], 0);
+        for &tied in &[1, 3, 4] {
+            assert!(wins[tied] > 800, "unfair tie break: {:?}", wins);
+        }
+    }
+
+    #[test]
+    fn repeated_contestant_is_not_favoured_by_the_tie() {
+        let evaluation = FloatDVector::from_vec(vec![7, 7]);
+        let mut rng = Random::seed_from_u64(44);
+        let selection = tournament(4, true, TieBreak::Random);
+        let drawn = [0, 0, 0, 1];
+        let mut tied = Vec::new();
+
+        let mut wins = [0usize; 2];
+        for _ in 0..2000 {
+            wins[selection.winner(|i, _| drawn[i], &evaluation, &mut tied, &mut rng)] += 1;
+        }
+        assert!(wins[1] > 800, "repeated contestant favoured: {:?}", wins);
+    }
+
+    #[test]
+    fn tie_break_is_deterministic_under_seed() {
+        let evaluation = FloatDVector::from_vec(vec![5; 40]);
+
+        for &tie_break in &[TieBreak::LowestIndex, TieBreak::Random] {
+            for &with_replacement in &[false, true] {
+                let selection = tournament(4, with_replacement, tie_break);
+                let winners = |seed| {
+                    let mut rng = Random::seed_from_u64(seed);
+                    (0..50)
+                        .map(|_| selection.select(&evaluation, &mut rng))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(winners(44), winners(44));
+            }
+        }
+    }
+
+    #[test]
+    fn validates_size() {
+        assert!(tournament(0, true, TieBreak::Random).validate(10).is_err());
+        assert!(tournament(11, false, TieBreak::Random)
+            .validate(10)
+            .is_err());
+        assert!(tournament(11, true, TieBreak::Random).validate(10).is_ok());
+        assert!(tournament(10, false, TieBreak::Random).validate(10).is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+use super::{bins, task::Task, DynamicResult, Float};
+use std::io::Write;
+
+/// Items selected by an individual, reported at the end of the run.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// indices of the selected items, in ascending order
+    pub items: Vec<usize>,
+    /// genes of the items in `items`: the number of their copies, or the number of their bin
+    pub genes: Vec<Float>,
+}
+
+impl Solution {
+    pub fn from_genome(genome: &[Float]) -> Self {
+        let (items, genes) = genome
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|&(_, gene)| gene != 0)
+            .unzip();
+
+        Self { items, genes }
+    }
+
+    /// Lists the selected items by their names, with their weight, size and cost,
+    /// and the number of their copies, if the items can be selected more than once,
+    /// or their bin in the multiple knapsack problem,
+    /// followed by the totals compared with the capacities, the contents of each bin,
+    /// the profit of the pairs of items,
+    /// the chosen option of each multiple choice group,
+    /// and the violated constraints of each kind, if the task has any constraints.
+    ///
+    /// Fails, without writing anything, if an item is not in the task,
+    /// or if a gene is not the number of a bin in the multiple knapsack problem.
+    pub fn write_report<W: Write>(&self, task: &Task, writer: &mut W) -> DynamicResult<()> {
+        self.validate(task)?;
+
+        let mut total_weight = 0u64;
+        let mut total_size = 0u64;
+        let mut total_cost = 0u64;
+
+        writer.write_fmt(format_args!("selected items: {}\n", self.items.len()))?;
+        let integer = task.get_upper_bounds().is_some();
+        let bins = task.get_bins();
+        writer.write_fmt(format_args!(
+            "{:<20} {:>10} {:>10} {:>10}",
+            "name", "weight", "size", "cost"
+        ))?;
+        if integer {
+            writer.write_fmt(format_args!(" {:>10}", "copies"))?;
+        }
+        if bins.is_some() {
+            writer.write_fmt(format_args!(" {:>10}", "bin"))?;
+        }
+        writer.write_fmt(format_args!("\n"))?;
+
+        // the genes of the multiple knapsack problem are the bins, each item is selected once
+        let copies_of = |gene: Float| if bins.is_some() { 1 } else { gene };
+
+        for (&item, &gene) in self.items.iter().zip(self.genes.iter()) {
+            let copies = copies_of(gene);
+            let weight = task.get_weights()[item];
+            let size = task.get_sizes()[item];
+            let cost = task.get_costs()[item];
+            total_weight += weight as u64 * copies as u64;
+            total_size += size as u64 * copies as u64;
+            total_cost += cost as u64 * copies as u64;
+
+            writer.write_fmt(format_args!(
+                "{:<20} {:>10} {:>10} {:>10}",
+                task.get_item_name(item),
+                weight,
+                size,
+                cost
+            ))?;
+            if integer {
+                writer.write_fmt(format_args!(" {:>10}", copies))?;
+            }
+            if let Some(bins) = bins {
+                // the genes are validated to be the numbers of the bins
+                writer.write_fmt(format_args!(" {:>10}", bins[gene as usize - 1].name))?;
+            }
+            writer.write_fmt(format_args!("\n"))?;
+        }
+
+        writer.write_fmt(format_args!(
+            "total weight: {} / {}; total size: {} / {}; total cost: {}\n",
+            total_weight, task.max_weight, total_size, task.max_size, total_cost
+        ))?;
+
+        if let Some(bins) = bins {
+            let loads = bins::loads(task, bins, |item| {
+                self.items
+                    .binary_search(&item)
+                    .map_or(0, |position| self.genes[position])
+            });
+
+            for (number, (bin, (weight, size))) in bins.iter().zip(loads).enumerate() {
+                let items: Vec<String> = self
+                    .items
+                    .iter()
+                    .zip(self.genes.iter())
+                    .filter(|(_, &gene)| gene as usize == number + 1)
+                    .map(|(&item, _)| task.get_item_name(item))
+                    .collect();
+                writer.write_fmt(format_args!(
+                    "bin {}: weight: {} / {}; size: {} / {}; items: {}\n",
+                    bin.name,
+                    weight,
+                    bin.max_weight,
+                    size,
+                    bin.max_size,
+                    items.join(" ")
+                ))?;
+            }
+        }
+
+        if !task.get_interactions().is_empty() {
+            let pairs_profit = task.get_interactions().profit(|item| {
+                self.items
+                    .binary_search(&item)
+                    .map_or(0, |position| copies_of(self.genes[position]))
+            });
+            writer.write_fmt(format_args!(
+                "profit of the pairs: {}; total profit: {}\n",
+                pairs_profit,
+                total_cost + pairs_profit
+            ))?;
+        }
+
+        let constraints = task.get_constraints();
+        let is_selected = |item| self.items.binary_search(&item).is_ok();
+
+        let chosen_options = constraints.chosen_options(is_selected);
+        for (group, chosen) in constraints.groups.iter().zip(chosen_options) {
+            let chosen = chosen.map_or_else(|| "-".to_string(), |item| task.get_item_name(item));
+            writer.write_fmt(format_args!("group {}: {}\n", group.name, chosen))?;
+        }
+
+        if !constraints.is_empty() {
+            let violations = constraints.violations(is_selected);
+            writer.write_fmt(format_args!("violated constraints: {}\n", violations))?;
+        }
+
+        Ok(())
+    }
+
+    // the report is written only for the items of the task and, with the bins, genes naming a bin
+    fn validate(&self, task: &Task) -> DynamicResult<()> {
+        if self.items.len() != self.genes.len() {
+            return Err(format!(
+                "solution has {} items, but {} genes",
+                self.items.len(),
+                self.genes.len()
+            )
+            .into());
+        }
+
+        for (&item, &gene) in self.items.iter().zip(self.genes.iter()) {
+            if item >= task.number_of_objects {
+                return Err(format!(
+                    "item {} is not in the task of {} items",
+                    item, task.number_of_objects
+                )
+                .into());
+            }
+            if let Some(bins) = task.get_bins() {
+                if gene == 0 || gene as usize > bins.len() {
+                    return Err(format!(
+                        "gene {} of item {} is not a bin, expected one in range [1, {}]",
+                        gene,
+                        task.get_item_name(item),
+                        bins.len()
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{bins::Bin, FloatDVector};
+
+    fn task() -> Task {
+        let bin = |name: &str| Bin {
+            name: name.to_string(),
+            max_weight: 10,
+            max_size: 10,
+        };
+
+        Task::from_args(
+            3,
+            0,
+            0,
+            FloatDVector::from_vec(vec![2, 3, 4]),
+            FloatDVector::from_vec(vec![1, 1, 1]),
+            FloatDVector::from_vec(vec![5, 6, 7]),
+        )
+        .with_item_names(vec!["tent".into(), "stove".into(), "rope".into()])
+        .with_bins(vec![bin("left"), bin("right")])
+    }
+
+    fn report(solution: &Solution) -> DynamicResult<String> {
+        let mut output = Vec::new();
+        solution.write_report(&task(), &mut output)?;
+
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn report_names_the_items_and_their_bins() {
+        let report = report(&Solution::from_genome(&[2, 0, 1])).unwrap();
+
+        assert!(report.starts_with("selected items: 2\n"));
+        assert!(report.contains("total weight: 6 / 20; total size: 2 / 20; total cost: 12\n"));
+        assert!(report.contains("bin left: weight: 4 / 10; size: 1 / 10; items: rope\n"));
+        assert!(report.contains("bin right: weight: 2 / 10; size: 1 / 10; items: tent\n"));
+        let tent = report
+            .lines()
+            .find(|line| line.starts_with("tent"))
+            .unwrap();
+        assert!(tent.ends_with("right"), "{}", tent);
+    }
+
+    #[test]
+    fn malformed_genes_are_reported_as_errors() {
+        // bin 3 does not exist, and a bin is never numbered 0
+        assert!(report(&Solution::from_genome(&[3, 0, 1])).is_err());
+        let unassigned = Solution {
+            items: vec![0],
+            genes: vec![0],
+        };
+        assert!(report(&unassigned).is_err());
+        let unknown_item = Solution {
+            items: vec![3],
+            genes: vec![1],
+        };
+        assert!(report(&unknown_item).is_err());
+    }
+}
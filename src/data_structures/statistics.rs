@@ -0,0 +1,62 @@
+use super::{DynamicResult, Float};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Values describing a single generation of the algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationStatistics {
+    /// the best fitness in the generation
+    pub best: Float,
+    /// mutation rate used to breed the next generation,
+    /// the mean over the population, if the rates are self-adaptive
+    pub mutation_rate: f64,
+    /// crossover rate used to breed the next generation,
+    /// the mean over the population, if the rates are self-adaptive
+    pub crossover_rate: f64,
+    /// fraction of the individuals, that are better than the better of their parents,
+    /// not known for the initial population
+    pub success_ratio: Option<f64>,
+    /// mean normalized Hamming distance between two individuals of the generation
+    pub diversity: f64,
+    /// fraction of the individuals, whose fitness has been found in the cache,
+    /// or computed for their copy, not known if the cache is disabled
+    pub cache_hit_rate: Option<f64>,
+}
+
+impl GenerationStatistics {
+    const CSV_HEADER: &'static str =
+        "generation,best,mutation_rate,crossover_rate,success_ratio,diversity,cache_hit_rate";
+
+    fn write_csv_row<W: Write>(&self, generation: usize, writer: &mut W) -> DynamicResult<()> {
+        writer.write_fmt(format_args!(
+            "{},{},{},{},{},{},{}\n",
+            generation,
+            self.best,
+            self.mutation_rate,
+            self.crossover_rate,
+            self.success_ratio
+                .map_or_else(String::new, |ratio| ratio.to_string()),
+            self.diversity,
+            self.cache_hit_rate
+                .map_or_else(String::new, |rate| rate.to_string())
+        ))?;
+
+        Ok(())
+    }
+}
+
+/// Stores the statistics of all generations as a csv file with a header.
+pub fn write_statistics<P: AsRef<Path>>(
+    path: P,
+    statistics: &[GenerationStatistics],
+) -> DynamicResult<()> {
+    let mut file = File::create(path)?;
+    file.write_fmt(format_args!("{}\n", GenerationStatistics::CSV_HEADER))?;
+
+    for (generation, generation_statistics) in statistics.iter().enumerate() {
+        generation_statistics.write_csv_row(generation, &mut file)?;
+    }
+
+    Ok(())
+}
@@ -1,13 +1,28 @@
-use super::FloatDVector;
+use super::{bins::Bin, constraints::Constraints, interactions::Interactions, Float, FloatDVector};
 
 #[derive(Debug, Clone)]
 pub struct Task {
     pub number_of_objects: usize,
     pub max_weight: usize,
     pub max_size: usize,
+    /// value of the optimal solution, if it is known from the instance file
+    pub known_optimum: Option<u64>,
     weights: FloatDVector,
     sizes: FloatDVector,
     costs: FloatDVector,
+    // second cost of each item, optimized as a separate objective by the pareto sub command
+    secondary_costs: Option<FloatDVector>,
+    // names or identifiers of the items, if they are given in the instance file
+    item_names: Option<Vec<String>>,
+    constraints: Constraints,
+    // profits of the pairs of items, earned in addition to the costs
+    interactions: Interactions,
+    // how many copies of each item can be selected, if the genes are integers instead of bits
+    upper_bounds: Option<Vec<Float>>,
+    // knapsacks of the multiple knapsack problem, the genes hold the numbers of the bins
+    bins: Option<Vec<Bin>>,
+    // largest number of the bin, repeated for each gene
+    bin_numbers: Vec<Float>,
 }
 
 impl Task {
@@ -23,12 +38,70 @@ impl Task {
             number_of_objects,
             max_weight,
             max_size,
+            known_optimum: None,
             weights,
             sizes,
             costs,
+            secondary_costs: None,
+            item_names: None,
+            constraints: Constraints::default(),
+            interactions: Interactions::default(),
+            upper_bounds: None,
+            bins: None,
+            bin_numbers: Vec::new(),
         }
     }
 
+    pub fn with_item_names(mut self, item_names: Vec<String>) -> Self {
+        self.item_names = Some(item_names);
+        self
+    }
+
+    pub fn with_secondary_costs(mut self, secondary_costs: FloatDVector) -> Self {
+        self.secondary_costs = Some(secondary_costs);
+        self
+    }
+
+    pub fn with_constraints(mut self, constraints: Constraints) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    pub fn with_interactions(mut self, interactions: Interactions) -> Self {
+        self.interactions = interactions;
+        self
+    }
+
+    /// Makes the genes integers, counting the copies of each item,
+    /// up to the given bound, or as many copies, as fit into the knapsack, if the bound is none.
+    pub fn with_upper_bounds(mut self, upper_bounds: Vec<Option<Float>>) -> Self {
+        let upper_bounds = upper_bounds
+            .into_iter()
+            .enumerate()
+            .map(|(item, bound)| {
+                bound.unwrap_or_else(|| {
+                    let weight = self.weights[item].max(1) as usize;
+                    let size = self.sizes[item].max(1) as usize;
+                    (self.max_weight / weight).min(self.max_size / size) as Float
+                })
+            })
+            .collect();
+
+        self.upper_bounds = Some(upper_bounds);
+        self
+    }
+
+    /// Turns the task into the multiple knapsack problem, with the given bins.
+    ///
+    /// The capacities of the task become the total capacities of the bins.
+    pub fn with_bins(mut self, bins: Vec<Bin>) -> Self {
+        self.max_weight = bins.iter().map(|bin| bin.max_weight).sum();
+        self.max_size = bins.iter().map(|bin| bin.max_size).sum();
+        self.bin_numbers = vec![bins.len() as Float; self.number_of_objects];
+        self.bins = Some(bins);
+        self
+    }
+
     pub fn get_weights(&self) -> &FloatDVector {
         &self.weights
     }
@@ -40,4 +113,231 @@ impl Task {
     pub fn get_costs(&self) -> &FloatDVector {
         &self.costs
     }
+
+    pub fn get_secondary_costs(&self) -> Option<&FloatDVector> {
+        self.secondary_costs.as_ref()
+    }
+
+    pub fn get_constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    pub fn get_interactions(&self) -> &Interactions {
+        &self.interactions
+    }
+
+    /// Total profit of the individual, which selects `copies(i)` copies of each item:
+    /// the costs of the items together with the profits of their pairs.
+    pub fn profit<F: Fn(usize) -> Float>(&self, copies: F) -> u64 {
+        let costs: u64 = (0..self.number_of_objects)
+            .map(|item| self.costs[item] as u64 * copies(item) as u64)
+            .sum();
+
+        costs + self.interactions.profit(copies)
+    }
+
+    /// Bounds of the integer genes, or none, if the genes are bits.
+    pub fn get_upper_bounds(&self) -> Option<&[Float]> {
+        self.upper_bounds.as_deref()
+    }
+
+    pub fn get_bins(&self) -> Option<&[Bin]> {
+        self.bins.as_deref()
+    }
+
+    /// Largest value of each gene, if the genes are not bits:
+    /// the bounds of the integer genes, or the number of the bins.
+    pub fn get_gene_bounds(&self) -> Option<&[Float]> {
+        match self.bins {
+            Some(_) => Some(&self.bin_numbers),
+            None => self.get_upper_bounds(),
+        }
+    }
+
+    /// Splits each item with the bound `k` into items with 1, 2, 4, ... copies of it,
+    /// so that any number of copies up to `k` can be selected, and the genes are bits again.
+    ///
+    /// Returns the binary task together with the original item and the number of copies
+    /// of each of its items.
+    pub fn binary_expansion(&self) -> (Task, Vec<(usize, Float)>) {
+        let upper_bounds = match self.upper_bounds.as_ref() {
+            Some(upper_bounds) => upper_bounds,
+            None => {
+                let copies = (0..self.number_of_objects).map(|item| (item, 1)).collect();
+                return (self.clone(), copies);
+            }
+        };
+
+        let mut copies: Vec<(usize, Float)> = Vec::new();
+        for (item, &bound) in upper_bounds.iter().enumerate() {
+            let mut remaining = bound;
+            let mut part = 1;
+            while remaining > 0 {
+                let count = part.min(remaining);
+                copies.push((item, count));
+                remaining -= count;
+                part *= 2;
+            }
+        }
+
+        let scaled = |values: &FloatDVector| {
+            FloatDVector::from_iterator(
+                copies.len(),
+                copies.iter().map(|&(item, count)| values[item] * count),
+            )
+        };
+        let mut task = Task::from_args(
+            copies.len(),
+            self.max_weight,
+            self.max_size,
+            scaled(&self.weights),
+            scaled(&self.sizes),
+            scaled(&self.costs),
+        );
+        if let Some(secondary_costs) = self.secondary_costs.as_ref() {
+            task = task.with_secondary_costs(scaled(secondary_costs));
+        }
+
+        if self.interactions.is_empty() {
+            return (task, copies);
+        }
+
+        // each part of an item interacts with each part of the items in the pairs of the item
+        let mut parts: Vec<Vec<usize>> = vec![Vec::new(); self.number_of_objects];
+        copies
+            .iter()
+            .enumerate()
+            .for_each(|(part, &(item, _))| parts[item].push(part));
+        let mut pairs = Vec::new();
+        for &(a, b, profit) in self.interactions.pairs() {
+            for &first in parts[a].iter() {
+                for &second in parts[b].iter() {
+                    pairs.push((first, second, profit * copies[first].1 * copies[second].1));
+                }
+            }
+        }
+        let interactions = Interactions::new(copies.len(), &pairs)
+            .expect("parts of the items in the pairs are valid items");
+
+        (task.with_interactions(interactions), copies)
+    }
+
+    /// Index of the item with the given name, or with the given index, if no item has such name.
+    pub fn find_item(&self, reference: &str) -> Option<usize> {
+        self.item_names
+            .as_ref()
+            .and_then(|names| names.iter().position(|name| name == reference))
+            .or_else(|| {
+                reference
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&item| item < self.number_of_objects)
+            })
+    }
+
+    /// Name of the item, or its index, if the items are not named.
+    pub fn get_item_name(&self, item: usize) -> String {
+        self.item_names
+            .as_ref()
+            .map_or_else(|| item.to_string(), |names| names[item].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::Random;
+    use rand::{Rng, SeedableRng};
+
+    const ITEMS: usize = 8;
+
+    fn bounded_task(rng: &mut Random) -> Task {
+        let mut values = || FloatDVector::from_fn(ITEMS, |_, _| rng.gen_range(1, 20));
+        let (weights, sizes, costs) = (values(), values(), values());
+        let upper_bounds = (0..ITEMS)
+            .map(|item| match item {
+                // a single copy, no copies at all, and as many copies, as fit
+                0 => Some(1),
+                1 => Some(0),
+                2 => None,
+                _ => Some(rng.gen_range(1, 10)),
+            })
+            .collect();
+        let interactions = Interactions::new(ITEMS, &[(0, 3, 5), (2, 4, 7), (4, 5, 1)]).unwrap();
+
+        Task::from_args(ITEMS, 100, 100, weights, sizes, costs)
+            .with_upper_bounds(upper_bounds)
+            .with_interactions(interactions)
+    }
+
+    // copies of each original item, selected by the bits of the expanded task
+    fn copies_of(bits: &[Float], copies: &[(usize, Float)]) -> Vec<Float> {
+        let mut counts = vec![0; ITEMS];
+        bits.iter()
+            .zip(copies.iter())
+            .for_each(|(&bit, &(item, count))| counts[item] += bit * count);
+        counts
+    }
+
+    #[test]
+    fn binary_expansion_covers_all_copies() {
+        let task = bounded_task(&mut Random::seed_from_u64(38));
+        let upper_bounds = task.get_upper_bounds().unwrap();
+        let (binary, copies) = task.binary_expansion();
+
+        assert_eq!(binary.number_of_objects, copies.len());
+        assert!(binary.get_upper_bounds().is_none());
+        for (item, &bound) in upper_bounds.iter().enumerate() {
+            let parts: Vec<Float> = copies
+                .iter()
+                .filter(|&&(original, _)| original == item)
+                .map(|&(_, count)| count)
+                .collect();
+            assert_eq!(parts.iter().sum::<Float>(), bound);
+
+            // any number of copies up to the bound is a sum of some of the parts
+            for target in 0..=bound {
+                let reachable = (0u32..1 << parts.len()).any(|subset| {
+                    parts
+                        .iter()
+                        .enumerate()
+                        .filter(|&(part, _)| (subset >> part) & 1 == 1)
+                        .map(|(_, &count)| count)
+                        .sum::<Float>()
+                        == target
+                });
+                assert!(reachable, "{} copies of item {}", target, item);
+            }
+        }
+    }
+
+    #[test]
+    fn binary_expansion_round_trips() {
+        let mut rng = Random::seed_from_u64(38);
+        let task = bounded_task(&mut rng);
+        let upper_bounds = task.get_upper_bounds().unwrap();
+        let (binary, copies) = task.binary_expansion();
+        let sum = |values: &FloatDVector, genes: &[Float]| -> Float {
+            values.iter().zip(genes.iter()).map(|(v, g)| v * g).sum()
+        };
+
+        for _ in 0..200 {
+            let bits: Vec<Float> = (0..copies.len()).map(|_| rng.gen_range(0, 2)).collect();
+            let counts = copies_of(&bits, &copies);
+
+            assert!(counts.iter().zip(upper_bounds.iter()).all(|(c, b)| c <= b));
+            assert_eq!(
+                sum(binary.get_weights(), &bits),
+                sum(task.get_weights(), &counts)
+            );
+            assert_eq!(
+                sum(binary.get_sizes(), &bits),
+                sum(task.get_sizes(), &counts)
+            );
+            assert_eq!(
+                binary.profit(|part| bits[part]),
+                task.profit(|item| counts[item])
+            );
+        }
+    }
 }
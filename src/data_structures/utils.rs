@@ -1,33 +1,142 @@
-use super::{Float, FloatDVector};
-use rand::{
-    distributions::{Distribution, Uniform},
-    prelude::ThreadRng,
-};
+use super::{Float, Random};
+use rand::Rng;
 
-#[allow(non_snake_case)]
-pub fn get_sparse_DVec_with(n: usize, k: usize, rng: &mut ThreadRng) -> FloatDVector {
-    let v = get_sparse_vec(n, k, rng);
+// up to this number of drawn indices, they are looked up by scanning, instead of marking them
+const SCAN_LIMIT: usize = 32;
 
-    FloatDVector::from_vec(v)
+// the Floyd's algorithm, which draws `k` different indices from range `0..n`
+// with exactly `k` random numbers, `insert` stores an index and tells, if it is a new one
+fn floyd<F: FnMut(usize) -> bool>(n: usize, k: usize, rng: &mut Random, mut insert: F) {
+    assert!(k <= n, "cannot draw {} different indices out of {}", k, n);
+
+    for j in n - k..n {
+        let candidate = rng.gen_range(0, j + 1);
+        // `j` cannot have been drawn yet, as the earlier draws were lower than `j`
+        if !insert(candidate) {
+            insert(j);
+        }
+    }
 }
 
-pub fn get_sparse_vec(n: usize, k: usize, rng: &mut ThreadRng) -> Vec<Float> {
-    let mut v: Vec<Float> = vec![0; n];
-    let dist = Uniform::from(0..n);
-    let mut one_counter: usize = 0;
+/// Converts a sum accumulated in `u64` to a gene value, saturating at the largest one,
+/// so a sum, which does not fit, is never wrapped around.
+pub fn saturate(sum: u64) -> Float {
+    sum.min(Float::MAX as u64) as Float
+}
 
-    while one_counter != k {
-        let pos_to_one = dist.sample(rng);
+/// Draws `k` different indices from range `0..n`, which takes exactly `k` random numbers
+/// for any `k <= n`.
+///
+/// Each set of the indices is equally likely, but their order is not random.
+pub fn sample_without_replacement(n: usize, k: usize, rng: &mut Random) -> Vec<usize> {
+    let mut sample: Vec<usize> = Vec::with_capacity(k);
+    sample_without_replacement_into(n, k, rng, &mut sample);
 
-        // here, we add one, as we suspect, we have got new `1` in our sparse vector
-        one_counter += 1;
+    sample
+}
 
-        // next, we subtract what was previously in the vec on our desired position.
-        // If it already was `1`, we will end up with not increasing our `one_counter`
-        one_counter -= v[pos_to_one] as usize;
+/// Same as `sample_without_replacement`, but the indices replace the content of `sample`,
+/// so a caller drawing repeatedly can reuse its allocation.
+pub fn sample_without_replacement_into(
+    n: usize,
+    k: usize,
+    rng: &mut Random,
+    sample: &mut Vec<usize>,
+) {
+    sample.clear();
 
-        v[pos_to_one] = 1 as Float;
+    if k <= SCAN_LIMIT {
+        floyd(n, k, rng, |index| {
+            let new = !sample.contains(&index);
+            if new {
+                sample.push(index);
+            }
+            new
+        });
+    } else {
+        let mut drawn = vec![false; n];
+        floyd(n, k, rng, |index| {
+            let new = !drawn[index];
+            if new {
+                drawn[index] = true;
+                sample.push(index);
+            }
+            new
+        });
     }
+}
+
+/// Vector of length `n` with ones on `k` random positions and zeros elsewhere.
+pub fn get_sparse_vec(n: usize, k: usize, rng: &mut Random) -> Vec<Float> {
+    let mut v: Vec<Float> = vec![0; n];
+
+    floyd(n, k, rng, |position| {
+        let new = v[position] == 0;
+        v[position] = 1;
+        new
+    });
 
     v
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // checks, that the sample has `k` different indices from range `0..n`
+    fn assert_sample(n: usize, k: usize, rng: &mut Random) {
+        let sample = sample_without_replacement(n, k, rng);
+        assert_eq!(sample.len(), k);
+
+        let mut drawn = vec![false; n];
+        for index in sample {
+            assert!(index < n, "index {} out of range 0..{}", index, n);
+            assert!(!drawn[index], "index {} drawn twice", index);
+            drawn[index] = true;
+        }
+    }
+
+    #[test]
+    fn samples_nothing() {
+        let mut rng = Random::seed_from_u64(44);
+        assert!(sample_without_replacement(0, 0, &mut rng).is_empty());
+        assert!(sample_without_replacement(10, 0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn samples_all_indices() {
+        let mut rng = Random::seed_from_u64(44);
+        for &n in &[1, SCAN_LIMIT, SCAN_LIMIT + 1, 200] {
+            let mut sample = sample_without_replacement(n, n, &mut rng);
+            sample.sort_unstable();
+            assert_eq!(sample, (0..n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn samples_distinct_indices_in_range() {
+        let mut rng = Random::seed_from_u64(44);
+        for _ in 0..100 {
+            assert_sample(50, 7, &mut rng);
+            assert_sample(SCAN_LIMIT, SCAN_LIMIT, &mut rng);
+            // above the limit the drawn indices are marked instead of scanned
+            assert_sample(1000, SCAN_LIMIT + 1, &mut rng);
+            assert_sample(1000, 400, &mut rng);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_more_indices_than_range() {
+        sample_without_replacement(3, 4, &mut Random::seed_from_u64(44));
+    }
+
+    #[test]
+    fn sparse_vec_has_k_ones() {
+        let mut rng = Random::seed_from_u64(44);
+        let v = get_sparse_vec(100, 37, &mut rng);
+        assert_eq!(v.iter().filter(|&&gene| gene == 1).count(), 37);
+        assert!(v.iter().all(|&gene| gene <= 1));
+    }
+}
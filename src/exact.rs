@@ -0,0 +1,406 @@
+use super::opt::SolveExact as SolveExactConfig;
+use crate::bounds::RelaxationBound;
+use crate::data_structures::{solution::Solution, task::Task, DynamicResult, Float};
+use crate::loader;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+// the dynamic programming needs one decision bit per item and per pair of capacities,
+// above this number of bits the branch and bound method is used instead
+const DP_DECISION_BITS_LIMIT: usize = 1 << 31;
+
+// the dynamic programming keeps the best value of each pair of capacities,
+// above this number of cells the branch and bound method is used instead, even for a few items
+const DP_CELLS_LIMIT: usize = 1 << 25;
+
+// how many nodes are visited between checks of the time limit
+const TIME_CHECK_INTERVAL: usize = 1 << 12;
+
+// the relaxation bound is a sum of fractions, so a bound equal to an integer
+// can be computed just below it, and it is raised by this margin before rounding it down
+const BOUND_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    DynamicProgramming,
+    BranchAndBound,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::DynamicProgramming => write!(f, "dynamic programming"),
+            Method::BranchAndBound => write!(f, "branch and bound"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExactSolution {
+    /// indices of the selected items, in ascending order
+    pub items: Vec<usize>,
+    pub value: u64,
+    /// false, if the time limit has been reached before the optimality was proven
+    pub optimal: bool,
+    pub method: Method,
+}
+
+impl ExactSolution {
+    fn from_items(mut items: Vec<usize>, task: &Task, optimal: bool, method: Method) -> Self {
+        items.sort_unstable();
+        Self {
+            value: task.profit(|item| items.binary_search(&item).is_ok() as Float),
+            items,
+            optimal,
+            method,
+        }
+    }
+}
+
+pub fn solve_exact(config: SolveExactConfig) -> DynamicResult<()> {
+    let task = loader::read(config.file_name.unwrap_or_else(|| "tasks.csv".to_string()))?;
+    let time_limit = Duration::from_secs_f64(config.time_limit);
+
+    if task.get_bins().is_some() {
+        return Err("exact solvers do not support multiple knapsacks".into());
+    }
+    if task.get_upper_bounds().is_some() && !task.get_constraints().is_empty() {
+        return Err("side constraints are not supported for items selected more than once".into());
+    }
+    // items, which can be selected more than once, are split into items selected at most once
+    let (binary_task, copies) = task.binary_expansion();
+
+    // the side constraints and the profits of the pairs are handled only by the branch and bound
+    let solution = if !config.branch_and_bound
+        && binary_task.get_constraints().is_empty()
+        && binary_task.get_interactions().is_empty()
+        && fits_dynamic_programming(&binary_task)
+    {
+        solve_dynamic_programming(&binary_task)
+    } else {
+        solve_branch_and_bound(&binary_task, time_limit)
+    };
+
+    let mut genome: Vec<Float> = vec![0; task.number_of_objects];
+    for &binary_item in solution.items.iter() {
+        let (item, count) = copies[binary_item];
+        genome[item] += count;
+    }
+    let report = Solution::from_genome(&genome);
+
+    println!("method: {}", solution.method);
+    println!("optimal: {}", solution.optimal);
+    println!("value: {}", solution.value);
+    report.write_report(&task, &mut std::io::stdout())?;
+
+    if let Some(output_file) = config.output_file {
+        let mut file = File::create(output_file)?;
+        file.write_fmt(format_args!("{}\n", solution.value))?;
+        for (item, copies) in report.items.iter().zip(report.genes.iter()) {
+            match task.get_upper_bounds() {
+                Some(_) => file.write_fmt(format_args!("{},{}\n", item, copies))?,
+                None => file.write_fmt(format_args!("{}\n", item))?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn fits_dynamic_programming(task: &Task) -> bool {
+    (task.max_weight + 1)
+        .checked_mul(task.max_size + 1)
+        .filter(|&cells| cells <= DP_CELLS_LIMIT)
+        .and_then(|cells| cells.checked_mul(task.number_of_objects.max(1)))
+        .is_some_and(|bits| bits <= DP_DECISION_BITS_LIMIT)
+}
+
+/// Solves the task with dynamic programming over both integer capacities.
+///
+/// Runs in `O(n * W * S)` time, so it should be used only if `fits_dynamic_programming` holds.
+pub fn solve_dynamic_programming(task: &Task) -> ExactSolution {
+    let columns = task.max_size + 1;
+    let cells = (task.max_weight + 1) * columns;
+    let mut best = vec![0u64; cells];
+    // decision bits, whether the item has been taken to reach the best value in the cell
+    let mut taken = vec![0u64; (task.number_of_objects * cells).div_ceil(64)];
+
+    for item in 0..task.number_of_objects {
+        let weight = task.get_weights()[item] as usize;
+        let size = task.get_sizes()[item] as usize;
+        let cost = task.get_costs()[item] as u64;
+
+        if weight > task.max_weight || size > task.max_size {
+            continue;
+        }
+
+        // iterate downwards, so each item is taken at most once
+        for w in (weight..=task.max_weight).rev() {
+            for s in (size..=task.max_size).rev() {
+                let candidate = best[(w - weight) * columns + s - size] + cost;
+                let cell = w * columns + s;
+
+                if candidate > best[cell] {
+                    best[cell] = candidate;
+                    let bit = item * cells + cell;
+                    taken[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+    }
+
+    let mut items = Vec::new();
+    let (mut w, mut s) = (task.max_weight, task.max_size);
+    for item in (0..task.number_of_objects).rev() {
+        let bit = item * cells + w * columns + s;
+        if taken[bit / 64] & (1 << (bit % 64)) != 0 {
+            items.push(item);
+            w -= task.get_weights()[item] as usize;
+            s -= task.get_sizes()[item] as usize;
+        }
+    }
+
+    ExactSolution::from_items(items, task, true, Method::DynamicProgramming)
+}
+
+/// Solves the task with depth first branch and bound, pruned with the linear relaxation bound.
+///
+/// Forbidden and conflicting items, and second items of a group are never taken, the remaining constraints
+/// are checked, when a complete solution is reached.
+/// If `time_limit` is exceeded, the best solution found so far is returned
+/// and it is not marked as optimal.
+pub fn solve_branch_and_bound(task: &Task, time_limit: Duration) -> ExactSolution {
+    let start = Instant::now();
+    let bound = RelaxationBound::new(task);
+
+    // branch on the items in the order of their surrogate efficiency,
+    // so the first descents find good solutions quickly
+    let order = bound.surrogate().order().to_vec();
+    let mut depth_of = vec![0usize; task.number_of_objects];
+    order
+        .iter()
+        .enumerate()
+        .for_each(|(depth, &item)| depth_of[item] = depth);
+
+    // the profits of the pairs, which are not decided yet at each depth, bound the profit,
+    // that the pairs can add to the linear relaxation
+    let interactions = task.get_interactions();
+    let mut open_pairs_profit = vec![0u64; order.len() + 1];
+    for &(a, b, profit) in interactions.pairs() {
+        open_pairs_profit[depth_of[a].max(depth_of[b])] += profit as u64;
+    }
+    for depth in (0..order.len()).rev() {
+        open_pairs_profit[depth] += open_pairs_profit[depth + 1];
+    }
+
+    let weights = task.get_weights();
+    let sizes = task.get_sizes();
+    let costs = task.get_costs();
+
+    let constraints = task.get_constraints();
+    let mut forbidden = vec![false; task.number_of_objects];
+    constraints
+        .forbidden
+        .iter()
+        .for_each(|&item| forbidden[item] = true);
+    let mut conflicts_of: Vec<Vec<usize>> = vec![Vec::new(); task.number_of_objects];
+    for &(a, b) in constraints.conflicts.iter() {
+        conflicts_of[a].push(b);
+        conflicts_of[b].push(a);
+    }
+    let mut selected = vec![false; task.number_of_objects];
+    // at most one item of each multiple choice group is taken
+    let mut group_of: Vec<Option<usize>> = vec![None; task.number_of_objects];
+    for (group, items) in constraints.groups.iter().enumerate() {
+        items
+            .items
+            .iter()
+            .for_each(|&item| group_of[item] = Some(group));
+    }
+    let mut group_taken = vec![false; constraints.groups.len()];
+
+    let mut best_value = 0u64;
+    let mut best_items: Vec<usize> = Vec::new();
+    // the empty solution is the initial one, unless it violates the constraints
+    let mut feasible_found = constraints.violations(|_| false).total() == 0;
+
+    // current path of the search: (depth, is the item at this depth taken)
+    let mut path: Vec<(usize, bool)> = Vec::with_capacity(order.len());
+    let mut weight = 0u64;
+    let mut size = 0u64;
+    let mut value = 0u64;
+    let mut depth = 0usize;
+    let mut visited = 0usize;
+    let mut optimal = true;
+
+    loop {
+        visited += 1;
+        if visited.is_multiple_of(TIME_CHECK_INTERVAL) && start.elapsed() > time_limit {
+            optimal = false;
+            break;
+        }
+
+        let remaining_weight = task.max_weight as u64 - weight;
+        let remaining_size = task.max_size as u64 - size;
+        let upper_bound = value as f64
+            + open_pairs_profit[depth] as f64
+            + bound.bound(task, remaining_weight, remaining_size, |item| {
+                depth_of[item] >= depth
+            });
+
+        // costs are integral, so the bound can be rounded down
+        let prune = feasible_found && (upper_bound + BOUND_TOLERANCE).floor() as u64 <= best_value;
+
+        if !prune && depth < order.len() {
+            let item = order[depth];
+            let fits = weights[item] as u64 <= remaining_weight
+                && sizes[item] as u64 <= remaining_size
+                && !forbidden[item]
+                && conflicts_of[item].iter().all(|&other| !selected[other])
+                && group_of[item].is_none_or(|group| !group_taken[group]);
+
+            // descend, taking the item if possible
+            if fits {
+                weight += weights[item] as u64;
+                size += sizes[item] as u64;
+                value +=
+                    costs[item] as u64 + interactions.delta(item, |other| selected[other] as Float);
+                selected[item] = true;
+                group_of[item]
+                    .iter()
+                    .for_each(|&group| group_taken[group] = true);
+            }
+            path.push((depth, fits));
+            depth += 1;
+            continue;
+        }
+
+        if !prune
+            && (value > best_value || !feasible_found)
+            && constraints.violations(|item| selected[item]).total() == 0
+        {
+            feasible_found = true;
+            best_value = value;
+            best_items = path
+                .iter()
+                .filter(|(_, taken)| *taken)
+                .map(|&(d, _)| order[d])
+                .collect();
+        }
+
+        // backtrack to the deepest taken item and try to skip it
+        let mut backtracked = false;
+        while let Some((d, taken)) = path.pop() {
+            if taken {
+                let item = order[d];
+                weight -= weights[item] as u64;
+                size -= sizes[item] as u64;
+                selected[item] = false;
+                value -=
+                    costs[item] as u64 + interactions.delta(item, |other| selected[other] as Float);
+                group_of[item]
+                    .iter()
+                    .for_each(|&group| group_taken[group] = false);
+                path.push((d, false));
+                depth = d + 1;
+                backtracked = true;
+                break;
+            }
+        }
+
+        if !backtracked {
+            break;
+        }
+    }
+
+    ExactSolution::from_items(best_items, task, optimal, Method::BranchAndBound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{FloatDVector, Random};
+    use crate::fixtures::{random_interactions, random_task, side_constraints};
+    use rand::SeedableRng;
+
+    const ITEMS: usize = 12;
+
+    // the best value of the feasible subsets of the items, if there is any
+    fn brute_force(task: &Task) -> Option<u64> {
+        (0u32..1 << ITEMS)
+            .filter_map(|subset| {
+                let selected = |item: usize| (subset >> item) & 1 == 1;
+                let sum = |values: &FloatDVector| -> usize {
+                    (0..ITEMS)
+                        .filter(|&item| selected(item))
+                        .map(|item| values[item] as usize)
+                        .sum()
+                };
+                let feasible = sum(task.get_weights()) <= task.max_weight
+                    && sum(task.get_sizes()) <= task.max_size
+                    && task.get_constraints().violations(selected).total() == 0;
+
+                if feasible {
+                    Some(task.profit(|item| selected(item) as Float))
+                } else {
+                    None
+                }
+            })
+            .max()
+    }
+
+    #[test]
+    fn dynamic_programming_equals_branch_and_bound() {
+        let mut rng = Random::seed_from_u64(27);
+
+        for _ in 0..30 {
+            let task = random_task(ITEMS, 1..30, 0.5, &mut rng);
+            let expected = brute_force(&task).unwrap();
+
+            let dp = solve_dynamic_programming(&task);
+            let bnb = solve_branch_and_bound(&task, Duration::from_secs(60));
+            assert!(dp.optimal && bnb.optimal);
+            assert_eq!(dp.value, expected);
+            assert_eq!(bnb.value, expected);
+        }
+    }
+
+    #[test]
+    fn large_capacities_are_routed_to_branch_and_bound() {
+        let mut rng = Random::seed_from_u64(27);
+        let task = random_task(ITEMS, 1..30, 0.5, &mut rng);
+        let (weights, sizes, costs) = (
+            task.get_weights().clone(),
+            task.get_sizes().clone(),
+            task.get_costs().clone(),
+        );
+        assert!(fits_dynamic_programming(&task));
+
+        // few decision bits, but too many cells of the best values
+        let large = Task::from_args(ITEMS, 1 << 13, 1 << 13, weights, sizes, costs);
+        let cells = (large.max_weight + 1) * (large.max_size + 1);
+        assert!(cells * ITEMS <= DP_DECISION_BITS_LIMIT);
+        assert!(!fits_dynamic_programming(&large));
+    }
+
+    #[test]
+    fn branch_and_bound_handles_interactions_and_constraints() {
+        let mut rng = Random::seed_from_u64(28);
+
+        for _ in 0..30 {
+            let task = random_task(ITEMS, 1..30, 0.5, &mut rng)
+                .with_interactions(random_interactions(ITEMS, 10, &mut rng))
+                .with_constraints(side_constraints());
+            let bnb = solve_branch_and_bound(&task, Duration::from_secs(60));
+            assert!(bnb.optimal);
+
+            if let Some(expected) = brute_force(&task) {
+                assert_eq!(bnb.value, expected);
+                let selected = |item: usize| bnb.items.binary_search(&item).is_ok();
+                assert_eq!(task.get_constraints().violations(selected).total(), 0);
+            }
+        }
+    }
+}
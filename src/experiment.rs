@@ -0,0 +1,100 @@
+use super::opt::Experiment as ExperimentConfig;
+use crate::algorithm::{self, Evolution};
+use crate::data_structures::{DynamicResult, Float};
+use crate::loader;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Outcome of a single trial of the experiment.
+pub struct Trial {
+    pub seed: u64,
+    /// best fitness of each generation
+    pub best: Vec<Float>,
+    /// number of the restarts of the population
+    pub restarts: usize,
+}
+
+/// Runs the independent trials of the algorithm, spread across the thread pool,
+/// and stores the mean best fitness of each generation in the output file.
+pub fn experiment(config: ExperimentConfig) -> DynamicResult<()> {
+    if config.trials == 0 {
+        return Err("number of trials has to be positive".into());
+    }
+
+    let task = loader::read(
+        config
+            .algorithm
+            .input_file
+            .as_ref()
+            .unwrap_or(&PathBuf::from("tasks.csv")),
+    )?;
+    let seed = config.algorithm.execution.seed();
+    println!("seed: {}", seed);
+
+    // the error cannot be sent from the thread pool, so it is passed as a message
+    let trials = config.algorithm.execution.thread_pool()?.install(|| {
+        (0..config.trials)
+            .into_par_iter()
+            .map(|trial| {
+                // the trials are repeated one by one with the seed of the experiment increased by their number
+                let mut algorithm = config.algorithm.clone();
+                algorithm.execution.seed = Some(seed.wrapping_add(trial as u64));
+
+                algorithm::evolve(&algorithm, &task)
+                    .map(|evolution: Evolution| Trial {
+                        seed: evolution.seed,
+                        best: evolution.statistics.iter().map(|s| s.best).collect(),
+                        restarts: evolution.restarts.len(),
+                    })
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<Vec<Trial>, String>>()
+    })?;
+
+    for (number, trial) in trials.iter().enumerate() {
+        println!(
+            "trial {}: seed: {}, best individual: {}, restarts: {}",
+            number,
+            trial.seed,
+            trial.best.iter().max().cloned().unwrap_or(0),
+            trial.restarts
+        );
+    }
+
+    let bests: Vec<f64> = trials
+        .iter()
+        .map(|trial| trial.best.iter().max().cloned().unwrap_or(0) as f64)
+        .collect();
+    let mean = bests.iter().sum::<f64>() / bests.len() as f64;
+    let deviation =
+        (bests.iter().map(|best| (best - mean).powi(2)).sum::<f64>() / bests.len() as f64).sqrt();
+    println!(
+        "best individual: mean: {:.2}, standard deviation: {:.2}, min: {}, max: {}",
+        mean,
+        deviation,
+        bests.iter().cloned().fold(f64::INFINITY, f64::min),
+        bests.iter().cloned().fold(0f64, f64::max)
+    );
+
+    let mut output_file = File::create(&config.algorithm.output_file)?;
+    for generation in 0..config.algorithm.iterations {
+        let mean = trials
+            .iter()
+            .map(|trial| trial.best[generation] as f64)
+            .sum::<f64>()
+            / trials.len() as f64;
+        output_file.write_fmt(format_args!("{}\n", mean))?;
+    }
+
+    if let Some(results_file) = &config.results_file {
+        let mut file = File::create(results_file)?;
+        file.write_fmt(format_args!("trial,seed,best\n"))?;
+        for (number, (trial, best)) in trials.iter().zip(bests.iter()).enumerate() {
+            file.write_fmt(format_args!("{},{},{}\n", number, trial.seed, best))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::data_structures::{
+    constraints::{Constraints, Group},
+    interactions::Interactions,
+    task::Task,
+    Float, FloatDVector,
+};
+use rand::Rng;
+use std::ops::Range;
+
+/// Task with the weights, sizes and costs drawn from `values`,
+/// whose capacities are the `capacity_share` of the total weight and of the total size.
+pub fn random_task<R: Rng>(
+    items: usize,
+    values: Range<Float>,
+    capacity_share: f64,
+    rng: &mut R,
+) -> Task {
+    let mut draw = || FloatDVector::from_fn(items, |_, _| rng.gen_range(values.start, values.end));
+    let (weights, sizes, costs) = (draw(), draw(), draw());
+    let capacity = |values: &FloatDVector| {
+        (values.iter().map(|&value| value as f64).sum::<f64>() * capacity_share) as usize
+    };
+    let (max_weight, max_size) = (capacity(&weights), capacity(&sizes));
+
+    Task::from_args(items, max_weight, max_size, weights, sizes, costs)
+}
+
+/// `pairs` random pairs of two different items, with the profits drawn from `profits`.
+pub fn random_pairs<R: Rng>(
+    items: usize,
+    pairs: usize,
+    profits: Range<Float>,
+    rng: &mut R,
+) -> Vec<(usize, usize, Float)> {
+    (0..pairs)
+        .map(|_| {
+            let a = rng.gen_range(0, items);
+            (
+                a,
+                (a + rng.gen_range(1, items)) % items,
+                rng.gen_range(profits.start, profits.end),
+            )
+        })
+        .collect()
+}
+
+/// Interactions of `pairs` random pairs of two different items, with the profits in [1, 100).
+pub fn random_interactions<R: Rng>(items: usize, pairs: usize, rng: &mut R) -> Interactions {
+    Interactions::new(items, &random_pairs(items, pairs, 1..100, rng))
+        .expect("the pairs refer to the items")
+}
+
+/// Constraints of each kind on the first eleven items: item 0 is mandatory, 1 is forbidden,
+/// 2 conflicts with 3 and 4 with 5, 6 requires 7, and one of 8, 9 and 10 is selected.
+pub fn side_constraints() -> Constraints {
+    Constraints {
+        mandatory: vec![0],
+        forbidden: vec![1],
+        conflicts: vec![(2, 3), (4, 5)],
+        requirements: vec![(6, 7)],
+        groups: vec![Group {
+            name: "group".to_string(),
+            items: vec![8, 9, 10],
+        }],
+    }
+}
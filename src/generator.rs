@@ -1,60 +1,467 @@
 use super::opt::Generate as GenerateConfig;
 use crate::data_structures::DynamicResult;
+use crate::instance::{
+    self, Format, GeneratorParameters, Instance, Metadata, Resource, SideConstraints,
+};
 use rand::distributions::{Distribution, Uniform};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
+use std::str::FromStr;
 
-fn generate_tasks(
-    n: usize,
-    max_w: usize,
-    max_s: usize,
-    multiplier: usize,
-    file_name: &str,
-) -> DynamicResult<(f64, f64)> {
-    let mut file = File::create(&file_name)?;
+// number of the items in the spanner set, and the largest multiplier of a spanner item
+const SPANNER_SIZE: usize = 2;
+const SPANNER_MULTIPLIER: usize = 10;
 
-    let mut rng = rand::thread_rng();
-    let weight_generator = Uniform::from(1f64..(multiplier as f64 * max_w as f64 / n as f64));
-    let size_generator = Uniform::from(1f64..(multiplier as f64 * max_s as f64 / n as f64));
-    let cost_generator = Uniform::from(1f64..n as f64);
+// without the tightness ratio, the items always exceed the capacities at least this many times
+const MIN_EXCESS: f64 = 2f64;
 
-    file.write_fmt(format_args!("{},{},{}\n", n, max_w, max_s))?;
-    let mut sum_weight = 0_f64;
-    let mut sum_size = 0_f64;
+/// Classic families of knapsack instances, which differ in the correlation
+/// of the costs and the resources used by the items.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstanceClass {
+    Uncorrelated,
+    WeaklyCorrelated,
+    StronglyCorrelated,
+    InverseStronglyCorrelated,
+    AlmostStronglyCorrelated,
+    SubsetSum,
+    Spanner,
+}
+
+impl FromStr for InstanceClass {
+    type Err = String;
 
-    for _ in 0..n {
-        let weight = weight_generator.sample(&mut rng);
-        let size = size_generator.sample(&mut rng);
-        let cost = cost_generator.sample(&mut rng);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uncorrelated" => Ok(InstanceClass::Uncorrelated),
+            "weakly-correlated" => Ok(InstanceClass::WeaklyCorrelated),
+            "strongly-correlated" => Ok(InstanceClass::StronglyCorrelated),
+            "inverse-strongly-correlated" => Ok(InstanceClass::InverseStronglyCorrelated),
+            "almost-strongly-correlated" => Ok(InstanceClass::AlmostStronglyCorrelated),
+            "subset-sum" => Ok(InstanceClass::SubsetSum),
+            "spanner" => Ok(InstanceClass::Spanner),
+            _ => Err(format!(
+                "unknown instance class: {}, expected one of: uncorrelated, weakly-correlated, \
+                 strongly-correlated, inverse-strongly-correlated, almost-strongly-correlated, \
+                 subset-sum, spanner",
+                s
+            )),
+        }
+    }
+}
 
-        sum_size += size;
-        sum_weight += weight;
+impl fmt::Display for InstanceClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            InstanceClass::Uncorrelated => "uncorrelated",
+            InstanceClass::WeaklyCorrelated => "weakly-correlated",
+            InstanceClass::StronglyCorrelated => "strongly-correlated",
+            InstanceClass::InverseStronglyCorrelated => "inverse-strongly-correlated",
+            InstanceClass::AlmostStronglyCorrelated => "almost-strongly-correlated",
+            InstanceClass::SubsetSum => "subset-sum",
+            InstanceClass::Spanner => "spanner",
+        };
 
-        file.write_fmt(format_args!("{},{},{}\n", weight, size, cost))?;
+        write!(f, "{}", name)
     }
+}
+
+/// weight, size and cost of a single item
+pub type Item = (f64, f64, f64);
 
-    Ok((sum_weight, sum_size))
+// Draws the items of the instance.
+//
+// The weights and sizes are uniform in their ranges, the cost range is [1, n].
+// In the correlated classes the cost follows the mean of the weight and the size,
+// both normalized to the cost range.
+struct ItemGenerator {
+    class: InstanceClass,
+    weight_range: f64,
+    size_range: f64,
+    cost_range: f64,
 }
 
-pub fn generate(config: GenerateConfig) -> DynamicResult<()> {
+impl ItemGenerator {
+    fn sample<R: Rng>(&self, class: InstanceClass, rng: &mut R) -> Item {
+        let r = self.cost_range;
+        let weight = Uniform::from(1f64..=self.weight_range).sample(rng);
+        let size = Uniform::from(1f64..=self.size_range).sample(rng);
+        let resource = (weight / self.weight_range + size / self.size_range) / 2f64 * r;
+
+        let cost = match class {
+            InstanceClass::Uncorrelated => Uniform::from(1f64..=r).sample(rng),
+            InstanceClass::WeaklyCorrelated => {
+                Uniform::from(resource - r / 10f64..=resource + r / 10f64).sample(rng)
+            }
+            InstanceClass::StronglyCorrelated | InstanceClass::Spanner => resource + r / 10f64,
+            InstanceClass::AlmostStronglyCorrelated => {
+                Uniform::from(resource + r / 10f64 - r / 500f64..=resource + r / 10f64 + r / 500f64)
+                    .sample(rng)
+            }
+            InstanceClass::SubsetSum => resource,
+            InstanceClass::InverseStronglyCorrelated => {
+                // the resources follow the cost here
+                let cost = Uniform::from(1f64..=r).sample(rng);
+                let resource = (cost + r / 10f64) / (r + r / 10f64);
+                return (
+                    round(resource * self.weight_range),
+                    round(resource * self.size_range),
+                    round(cost),
+                );
+            }
+        };
+
+        (round(weight), round(size), round(cost))
+    }
+
+    fn generate<R: Rng>(&self, n: usize, rng: &mut R) -> Vec<Item> {
+        if self.class != InstanceClass::Spanner {
+            return (0..n).map(|_| self.sample(self.class, rng)).collect();
+        }
+
+        // each item is a multiple of one of a few strongly correlated, scaled down spanner items
+        let scale = 2f64 / SPANNER_MULTIPLIER as f64;
+        let spanner: Vec<Item> = (0..SPANNER_SIZE)
+            .map(|_| {
+                let (weight, size, cost) = self.sample(InstanceClass::StronglyCorrelated, rng);
+                (
+                    round(weight * scale),
+                    round(size * scale),
+                    round(cost * scale),
+                )
+            })
+            .collect();
+
+        (0..n)
+            .map(|_| {
+                let (weight, size, cost) = spanner[rng.gen_range(0, SPANNER_SIZE)];
+                let multiplier = rng.gen_range(1, SPANNER_MULTIPLIER + 1) as f64;
+                (weight * multiplier, size * multiplier, cost * multiplier)
+            })
+            .collect()
+    }
+}
+
+// items are stored as positive integers
+fn round(value: f64) -> f64 {
+    value.round().max(1f64)
+}
+
+/// Instance built in memory, before it is stored.
+pub struct GeneratedInstance {
+    pub max_weight: usize,
+    pub max_size: usize,
+    /// weight, size and cost of each item
+    pub items: Vec<Item>,
+}
+
+impl GeneratedInstance {
+    pub fn total_weight(&self) -> f64 {
+        self.items.iter().map(|(weight, _, _)| weight).sum()
+    }
+
+    pub fn total_size(&self) -> f64 {
+        self.items.iter().map(|(_, size, _)| size).sum()
+    }
+
+    fn into_instance(self, parameters: GeneratorParameters) -> Instance {
+        Instance {
+            resources: vec![
+                Resource {
+                    name: "weight".to_string(),
+                    capacity: self.max_weight,
+                },
+                Resource {
+                    name: "size".to_string(),
+                    capacity: self.max_size,
+                },
+            ],
+            items: self
+                .items
+                .into_iter()
+                .enumerate()
+                .map(|(i, (weight, size, cost))| instance::Item {
+                    id: Some(i.to_string()),
+                    label: None,
+                    group: None,
+                    bound: None,
+                    unbounded: false,
+                    cost,
+                    secondary_cost: None,
+                    usage: vec![weight, size],
+                })
+                .collect(),
+            constraints: SideConstraints::default(),
+            interactions: Vec::new(),
+            bins: Vec::new(),
+            metadata: Metadata {
+                generator: Some(parameters),
+                known_optimum: None,
+            },
+        }
+    }
+
+    fn write_csv(&self, file_name: &str) -> DynamicResult<()> {
+        let mut file = File::create(file_name)?;
+        file.write_fmt(format_args!(
+            "{},{},{}\n",
+            self.items.len(),
+            self.max_weight,
+            self.max_size
+        ))?;
+        for (weight, size, cost) in self.items.iter() {
+            file.write_fmt(format_args!("{},{},{}\n", weight, size, cost))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Generates the instance in a single pass.
+///
+/// With the tightness ratio, the capacities are the given fraction of the total weight and size.
+/// Otherwise the capacities are taken from the config, and the items are drawn again
+/// from the scaled up weight or size range, if they would not exceed twice the capacities.
+pub fn generate_instance<R: Rng>(config: &GenerateConfig, rng: &mut R) -> GeneratedInstance {
     let n = config.number_of_items;
-    let max_w = config.max_weight;
-    let max_s = config.max_size;
-    let multiplier = config.multiplier;
-    let file_name = config.output_file.unwrap_or("tasks.csv".to_string());
-
-    while {
-        let (sum_weight, sum_size) = generate_tasks(n, max_w, max_s, multiplier, &file_name)?;
-        println!(
-            "weight: {} | {}; size: {} | {}",
-            sum_weight,
-            10 * max_w,
-            sum_size,
-            10 * max_s
-        );
-
-        sum_weight <= 2_f64 * max_w as f64 || sum_size <= 2_f64 * max_s as f64
-    } {}
+    let multiplier = config.multiplier as f64;
+
+    let mut generator = ItemGenerator {
+        class: config.class,
+        weight_range: (multiplier * config.max_weight as f64 / n as f64).max(1f64),
+        size_range: (multiplier * config.max_size as f64 / n as f64).max(1f64),
+        cost_range: (n as f64).max(1f64),
+    };
+    let mut instance = GeneratedInstance {
+        max_weight: config.max_weight,
+        max_size: config.max_size,
+        items: generator.generate(n, rng),
+    };
+
+    match config.tightness {
+        Some(tightness) => {
+            instance.max_weight = (tightness * instance.total_weight()).floor() as usize;
+            instance.max_size = (tightness * instance.total_size()).floor() as usize;
+        }
+        None => loop {
+            let weight_scale = MIN_EXCESS * config.max_weight as f64 / instance.total_weight();
+            let size_scale = MIN_EXCESS * config.max_size as f64 / instance.total_size();
+            if weight_scale <= 1f64 && size_scale <= 1f64 {
+                break;
+            }
+
+            // scaling the drawn items would break the correlation of their costs,
+            // so the costs are drawn again together with the scaled weights and sizes
+            generator.weight_range *= weight_scale.max(1f64);
+            generator.size_range *= size_scale.max(1f64);
+            instance.items = generator.generate(n, rng);
+        },
+    }
+
+    instance
+}
+
+pub fn generate(config: GenerateConfig) -> DynamicResult<()> {
+    let file_name = config
+        .output_file
+        .clone()
+        .unwrap_or_else(|| "tasks.csv".to_string());
+
+    if let Some(tightness) = config.tightness {
+        if tightness <= 0f64 || tightness >= 1f64 {
+            return Err("tightness ratio has to be in range (0, 1)".into());
+        }
+    }
+    if config.number_of_items == 0 {
+        return Err("at least one item has to be generated".into());
+    }
+
+    // print the seed, so the instance can be generated again
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    println!("seed: {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let instance = generate_instance(&config, &mut rng);
+    let tightness = (
+        instance.max_weight as f64 / instance.total_weight(),
+        instance.max_size as f64 / instance.total_size(),
+    );
+    println!(
+        "weight tightness: {:.4} ({} / {}); size tightness: {:.4} ({} / {})",
+        tightness.0,
+        instance.max_weight,
+        instance.total_weight(),
+        tightness.1,
+        instance.max_size,
+        instance.total_size(),
+    );
+
+    match Format::from_path(&file_name) {
+        Format::Csv => instance.write_csv(&file_name)?,
+        Format::Json | Format::Yaml => instance
+            .into_instance(GeneratorParameters {
+                class: config.class.to_string(),
+                seed,
+                multiplier: config.multiplier,
+                tightness: config.tightness,
+            })
+            .write(&file_name)?,
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLASSES: [InstanceClass; 7] = [
+        InstanceClass::Uncorrelated,
+        InstanceClass::WeaklyCorrelated,
+        InstanceClass::StronglyCorrelated,
+        InstanceClass::InverseStronglyCorrelated,
+        InstanceClass::AlmostStronglyCorrelated,
+        InstanceClass::SubsetSum,
+        InstanceClass::Spanner,
+    ];
+
+    fn config(class: InstanceClass, multiplier: usize, tightness: Option<f64>) -> GenerateConfig {
+        GenerateConfig {
+            number_of_items: 100,
+            max_weight: 1000,
+            max_size: 500,
+            multiplier,
+            output_file: None,
+            class,
+            tightness,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn costs_follow_the_resources_of_each_class() {
+        let r = 100f64;
+        let (weight_range, size_range) = (1000f64, 2000f64);
+        // the rounding of the weight, the size and the cost moves them by at most this much
+        let tolerance = 1f64;
+
+        for &class in CLASSES.iter() {
+            let generator = ItemGenerator {
+                class,
+                weight_range,
+                size_range,
+                cost_range: r,
+            };
+            let items = generator.generate(200, &mut StdRng::seed_from_u64(32));
+            let resource =
+                |&(weight, size, _): &Item| (weight / weight_range + size / size_range) / 2f64 * r;
+
+            for item in items.iter() {
+                let cost = item.2;
+                let holds = match class {
+                    InstanceClass::Uncorrelated => cost >= 1f64 && cost <= r,
+                    InstanceClass::WeaklyCorrelated => {
+                        (cost - resource(item)).abs() <= r / 10f64 + tolerance
+                    }
+                    InstanceClass::StronglyCorrelated => {
+                        (cost - resource(item) - r / 10f64).abs() <= tolerance
+                    }
+                    InstanceClass::AlmostStronglyCorrelated => {
+                        (cost - resource(item) - r / 10f64).abs() <= r / 500f64 + tolerance
+                    }
+                    InstanceClass::SubsetSum => (cost - resource(item)).abs() <= tolerance,
+                    InstanceClass::InverseStronglyCorrelated => {
+                        // the resources follow the cost before it is rounded
+                        let share = (cost + r / 10f64) / (r + r / 10f64);
+                        let slack = 0.5 / (r + r / 10f64);
+                        (item.0 - share * weight_range).abs() <= slack * weight_range + tolerance
+                            && (item.1 - share * size_range).abs() <= slack * size_range + tolerance
+                    }
+                    // the multiples of a spanner item are checked below
+                    InstanceClass::Spanner => true,
+                };
+                assert!(holds, "{} item {:?} breaks the correlation", class, item);
+            }
+
+            if class == InstanceClass::Spanner {
+                // a multiple keeps the ratios of its spanner item
+                let mut ratios: Vec<(f64, f64)> = items
+                    .iter()
+                    .map(|&(weight, size, cost)| (weight / cost, size / cost))
+                    .collect();
+                ratios.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                ratios.dedup();
+                assert!(ratios.len() <= SPANNER_SIZE, "{:?}", ratios);
+            }
+        }
+    }
+
+    #[test]
+    fn tightness_sets_the_capacities() {
+        for &class in CLASSES.iter() {
+            let instance = generate_instance(
+                &config(class, 10, Some(0.3)),
+                &mut StdRng::seed_from_u64(32),
+            );
+            assert_eq!(
+                instance.max_weight,
+                (0.3 * instance.total_weight()).floor() as usize
+            );
+            assert_eq!(
+                instance.max_size,
+                (0.3 * instance.total_size()).floor() as usize
+            );
+        }
+    }
+
+    #[test]
+    fn items_exceed_the_capacities_without_tightness() {
+        // with the multiplier of one, the first items total about half of the capacities
+        for &class in CLASSES.iter() {
+            let config = config(class, 1, None);
+            let instance = generate_instance(&config, &mut StdRng::seed_from_u64(32));
+            assert_eq!(
+                (instance.max_weight, instance.max_size),
+                (config.max_weight, config.max_size)
+            );
+            assert!(instance.total_weight() >= MIN_EXCESS * config.max_weight as f64);
+            assert!(instance.total_size() >= MIN_EXCESS * config.max_size as f64);
+        }
+    }
+
+    #[test]
+    fn generated_instances_round_trip_through_json_and_yaml() {
+        for extension in &["json", "yaml"] {
+            let path = std::env::temp_dir().join(format!(
+                "generated-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let mut config = config(InstanceClass::WeaklyCorrelated, 10, Some(0.4));
+            config.seed = Some(33);
+            config.output_file = Some(path.to_str().unwrap().to_string());
+
+            let expected = generate_instance(&config, &mut StdRng::seed_from_u64(33));
+            generate(config).unwrap();
+            let instance = Instance::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            let parameters = instance.metadata.generator.clone().unwrap();
+            assert_eq!(parameters.class, "weakly-correlated");
+            assert_eq!(parameters.seed, 33);
+            assert_eq!(parameters.tightness, Some(0.4));
+
+            let task = instance.into_task().unwrap();
+            assert_eq!(task.max_weight, expected.max_weight);
+            assert_eq!(task.max_size, expected.max_size);
+            for (i, &(weight, size, cost)) in expected.items.iter().enumerate() {
+                assert_eq!(task.get_weights()[i] as f64, weight);
+                assert_eq!(task.get_sizes()[i] as f64, size);
+                assert_eq!(task.get_costs()[i] as f64, cost);
+            }
+            assert_eq!(task.number_of_objects, expected.items.len());
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use crate::data_structures::{
+    bins::Bin,
+    constraints::{Constraints, Group},
+    interactions::Interactions,
+    task::Task,
+    DynamicResult, Float, FloatDVector,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// Format of the file with an instance, detected by its extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => Format::Json,
+            Some("yaml") | Some("yml") => Format::Yaml,
+            _ => Format::Csv,
+        }
+    }
+}
+
+/// Self describing instance, stored as JSON or YAML.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Instance {
+    /// constrained resources of the knapsack, the first one is the weight, the second one the size
+    pub resources: Vec<Resource>,
+    pub items: Vec<Item>,
+    #[serde(default, skip_serializing_if = "SideConstraints::is_empty")]
+    pub constraints: SideConstraints,
+    /// profits of the pairs of items, earned in addition to their costs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interactions: Vec<Interaction>,
+    /// knapsacks of the multiple knapsack problem, the capacities of the `resources` are ignored,
+    /// if they are given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bins: Vec<BinDescription>,
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Resource {
+    pub name: String,
+    pub capacity: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Item {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// group of the multiple choice, exactly one item of each group is selected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// how many copies of the item can be selected, at most one, unless it is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bound: Option<u32>,
+    /// the item can be selected any number of times, overrides the `bound`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unbounded: bool,
+    pub cost: f64,
+    /// second cost of the item, for the secondary-cost objective of the pareto sub command,
+    /// has to be given for all items or for none of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secondary_cost: Option<f64>,
+    /// usage of each resource, in the order of `Instance::resources`
+    pub usage: Vec<f64>,
+}
+
+/// Item referred to by its id, its label, or its index in `Instance::items`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ItemReference {
+    Index(usize),
+    Name(String),
+}
+
+/// Knapsack of the multiple knapsack problem, see `Bin`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BinDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// capacity of each resource, in the order of `Instance::resources`
+    pub capacity: Vec<usize>,
+}
+
+/// Profit of a pair of items, see `Interactions`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Interaction {
+    pub items: (ItemReference, ItemReference),
+    pub profit: f64,
+}
+
+/// Constraints on the selection of the items, see `Constraints`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SideConstraints {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mandatory: Vec<ItemReference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden: Vec<ItemReference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<(ItemReference, ItemReference)>,
+    /// pairs of an item and the item, it requires
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<(ItemReference, ItemReference)>,
+}
+
+impl SideConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.mandatory.is_empty()
+            && self.forbidden.is_empty()
+            && self.conflicts.is_empty()
+            && self.requires.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<GeneratorParameters>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_optimum: Option<u64>,
+}
+
+/// Arguments of the `generate` sub command, which created the instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeneratorParameters {
+    pub class: String,
+    pub seed: u64,
+    pub multiplier: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tightness: Option<f64>,
+}
+
+/// Converts a value of the instance, such as a cost or a usage, to the integral `Float`,
+/// rejecting the fractional, negative and too large values instead of truncating them.
+pub fn integral_value(value: f64) -> DynamicResult<Float> {
+    if value.fract() != 0f64 || value < 0f64 || value > Float::MAX as f64 {
+        return Err(format!("{} is not an integer in range [0, {}]", value, Float::MAX).into());
+    }
+
+    Ok(value as Float)
+}
+
+impl Instance {
+    pub fn read<P: AsRef<Path>>(path: P) -> DynamicResult<Self> {
+        let reader = BufReader::new(File::open(&path)?);
+
+        match Format::from_path(&path) {
+            Format::Json => Ok(serde_json::from_reader(reader)?),
+            Format::Yaml => Ok(serde_yaml::from_reader(reader)?),
+            Format::Csv => Err("csv instances are read by the loader".into()),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> DynamicResult<()> {
+        let writer = BufWriter::new(File::create(&path)?);
+
+        match Format::from_path(&path) {
+            Format::Json => serde_json::to_writer_pretty(writer, self)?,
+            Format::Yaml => serde_yaml::to_writer(writer, self)?,
+            Format::Csv => return Err("csv instances are written by the generator".into()),
+        }
+
+        Ok(())
+    }
+
+    pub fn into_task(self) -> DynamicResult<Task> {
+        if self.resources.len() != 2 {
+            return Err(format!(
+                "expected exactly two resources (weight and size), got {}",
+                self.resources.len()
+            )
+            .into());
+        }
+
+        let mut weights: Vec<Float> = Vec::with_capacity(self.items.len());
+        let mut sizes: Vec<Float> = Vec::with_capacity(self.items.len());
+        let mut costs: Vec<Float> = Vec::with_capacity(self.items.len());
+
+        for (i, item) in self.items.iter().enumerate() {
+            let id = || item.id.clone().unwrap_or_else(|| i.to_string());
+            // the weight and the size are read from the first two entries
+            if item.usage.len() != self.resources.len() {
+                return Err(format!(
+                    "item {} uses {} resources, expected {}",
+                    id(),
+                    item.usage.len(),
+                    self.resources.len()
+                )
+                .into());
+            }
+            let value = |value: f64, name: &str| -> DynamicResult<Float> {
+                integral_value(value)
+                    .map_err(|e| format!("{} of item {}: {}", name, id(), e).into())
+            };
+
+            weights.push(value(item.usage[0], "weight")?);
+            sizes.push(value(item.usage[1], "size")?);
+            costs.push(value(item.cost, "cost")?);
+        }
+
+        let mut task = Task::from_args(
+            self.items.len(),
+            self.resources[0].capacity,
+            self.resources[1].capacity,
+            FloatDVector::from_vec(weights),
+            FloatDVector::from_vec(sizes),
+            FloatDVector::from_vec(costs),
+        );
+        task.known_optimum = self.metadata.known_optimum;
+        if let Some(secondary_costs) = self.secondary_costs()? {
+            task = task.with_secondary_costs(secondary_costs);
+        }
+        task = task
+            .with_constraints(self.resolve_constraints()?)
+            .with_interactions(self.resolve_interactions()?);
+
+        if self
+            .items
+            .iter()
+            .any(|item| item.bound.is_some() || item.unbounded)
+        {
+            let upper_bounds = self
+                .items
+                .iter()
+                .map(|item| {
+                    if item.unbounded {
+                        None
+                    } else {
+                        Some(item.bound.unwrap_or(1) as Float)
+                    }
+                })
+                .collect();
+            task = task.with_upper_bounds(upper_bounds);
+        }
+
+        if !self.bins.is_empty() {
+            if task.get_upper_bounds().is_some() {
+                return Err("items selected more than once cannot be assigned to bins".into());
+            }
+            task = task.with_bins(self.resolve_bins()?);
+        }
+
+        if self
+            .items
+            .iter()
+            .all(|item| item.label.is_none() && item.id.is_none())
+        {
+            return Ok(task);
+        }
+
+        let names = self
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| item.label.or(item.id).unwrap_or_else(|| i.to_string()))
+            .collect();
+
+        Ok(task.with_item_names(names))
+    }
+
+    // none, if no item has a secondary cost
+    fn secondary_costs(&self) -> DynamicResult<Option<FloatDVector>> {
+        if self.items.iter().all(|item| item.secondary_cost.is_none()) {
+            return Ok(None);
+        }
+
+        let costs = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let id = || item.id.clone().unwrap_or_else(|| i.to_string());
+                match item.secondary_cost {
+                    Some(cost) => integral_value(cost)
+                        .map_err(|e| format!("secondary cost of item {}: {}", id(), e).into()),
+                    None => Err(format!("item {} has no secondary cost", id()).into()),
+                }
+            })
+            .collect::<DynamicResult<Vec<Float>>>()?;
+
+        Ok(Some(FloatDVector::from_vec(costs)))
+    }
+
+    fn resolve(&self, reference: &ItemReference) -> DynamicResult<usize> {
+        match reference {
+            ItemReference::Index(index) => Ok(*index),
+            ItemReference::Name(name) => self
+                .items
+                .iter()
+                .position(|item| {
+                    item.id.as_ref() == Some(name) || item.label.as_ref() == Some(name)
+                })
+                .ok_or_else(|| format!("unknown item: {}", name).into()),
+        }
+    }
+
+    fn resolve_bins(&self) -> DynamicResult<Vec<Bin>> {
+        self.bins
+            .iter()
+            .enumerate()
+            .map(|(number, bin)| {
+                if bin.capacity.len() != self.resources.len() {
+                    return Err(format!(
+                        "bin {} has {} capacities, expected {}",
+                        number + 1,
+                        bin.capacity.len(),
+                        self.resources.len()
+                    )
+                    .into());
+                }
+
+                Ok(Bin {
+                    name: bin.name.clone().unwrap_or_else(|| (number + 1).to_string()),
+                    max_weight: bin.capacity[0],
+                    max_size: bin.capacity[1],
+                })
+            })
+            .collect()
+    }
+
+    fn resolve_interactions(&self) -> DynamicResult<Interactions> {
+        let pairs = self
+            .interactions
+            .iter()
+            .map(|interaction| {
+                Ok((
+                    self.resolve(&interaction.items.0)?,
+                    self.resolve(&interaction.items.1)?,
+                    integral_value(interaction.profit)
+                        .map_err(|e| format!("profit of an interaction: {}", e))?,
+                ))
+            })
+            .collect::<DynamicResult<Vec<_>>>()?;
+
+        Interactions::new(self.items.len(), &pairs)
+    }
+
+    fn resolve_constraints(&self) -> DynamicResult<Constraints> {
+        let resolve = |reference: &ItemReference| self.resolve(reference);
+        let resolve_pair = |(a, b): &(ItemReference, ItemReference)| -> DynamicResult<_> {
+            Ok((resolve(a)?, resolve(b)?))
+        };
+
+        let constraints = Constraints {
+            mandatory: self
+                .constraints
+                .mandatory
+                .iter()
+                .map(resolve)
+                .collect::<DynamicResult<_>>()?,
+            forbidden: self
+                .constraints
+                .forbidden
+                .iter()
+                .map(resolve)
+                .collect::<DynamicResult<_>>()?,
+            conflicts: self
+                .constraints
+                .conflicts
+                .iter()
+                .map(resolve_pair)
+                .collect::<DynamicResult<_>>()?,
+            requirements: self
+                .constraints
+                .requires
+                .iter()
+                .map(resolve_pair)
+                .collect::<DynamicResult<_>>()?,
+            groups: Group::from_item_groups(
+                &self
+                    .items
+                    .iter()
+                    .map(|item| item.group.clone())
+                    .collect::<Vec<_>>(),
+            ),
+        };
+
+        constraints.validate(self.items.len())?;
+        Ok(constraints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(items: &str) -> Instance {
+        serde_json::from_str(&format!(
+            r#"{{
+                "resources": [{{"name": "weight", "capacity": 10}}, {{"name": "size", "capacity": 10}}],
+                "items": {}
+            }}"#,
+            items
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn integral_values_are_accepted() {
+        assert_eq!(integral_value(0f64).unwrap(), 0);
+        assert_eq!(integral_value(42f64).unwrap(), 42);
+        assert_eq!(integral_value(Float::MAX as f64).unwrap(), Float::MAX);
+    }
+
+    #[test]
+    fn other_values_are_rejected() {
+        for &value in &[
+            1.5,
+            -1f64,
+            Float::MAX as f64 + 1f64,
+            f64::NAN,
+            f64::INFINITY,
+        ] {
+            assert!(integral_value(value).is_err(), "{} accepted", value);
+        }
+    }
+
+    #[test]
+    fn items_are_validated() {
+        assert!(instance(r#"[{"cost": 3, "usage": [1, 2]}]"#)
+            .into_task()
+            .is_ok());
+        assert!(instance(r#"[{"cost": 3, "usage": [1]}]"#)
+            .into_task()
+            .is_err());
+        assert!(instance(r#"[{"cost": 2.5, "usage": [1, 2]}]"#)
+            .into_task()
+            .is_err());
+        assert!(instance(r#"[{"cost": 3, "usage": [-1, 2]}]"#)
+            .into_task()
+            .is_err());
+        assert!(instance(r#"[{"cost": 3, "usage": [1, 1e12]}]"#)
+            .into_task()
+            .is_err());
+    }
+}
@@ -0,0 +1,16 @@
+#[macro_use]
+extern crate structopt_derive;
+
+pub mod algorithm;
+pub mod bounds;
+pub mod data_structures;
+pub mod exact;
+pub mod experiment;
+// random tasks shared by the tests and the benchmarks, not a part of the interface
+#[doc(hidden)]
+pub mod fixtures;
+pub mod generator;
+pub mod instance;
+pub mod loader;
+pub mod nsga;
+pub mod opt;
@@ -1,12 +1,65 @@
-use super::data_structures::{task::Task, FloatDVector};
+use super::data_structures::{
+    bins::Bin,
+    constraints::{Constraints, Group},
+    interactions::Interactions,
+    task::Task,
+    FloatDVector,
+};
 use crate::data_structures::{DynamicResult, Float};
+use crate::instance::{integral_value, Format, Instance};
 use std::{
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
 };
 
+/// Reads the task from a csv file, or from a JSON or YAML instance, detected by the extension.
 pub fn read<P: AsRef<Path>>(input_file: P) -> DynamicResult<Task> {
+    match Format::from_path(&input_file) {
+        Format::Csv => read_csv(input_file),
+        Format::Json | Format::Yaml => Instance::read(input_file)?.into_task(),
+    }
+}
+
+// columns of the lines describing the items
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Weight,
+    Size,
+    Cost,
+    Name,
+    /// group of the multiple choice, the item belongs to
+    Group,
+    /// how many copies of the item can be selected, `unbounded` if there is no limit
+    Bound,
+    /// second cost of the item, for the secondary-cost objective of the pareto sub command
+    SecondaryCost,
+}
+
+// bound of the items, which can be selected any number of times
+const UNBOUNDED: &str = "unbounded";
+
+// columns used, if the file does not contain a header, the name column is optional
+const DEFAULT_COLUMNS: [Column; 4] = [Column::Weight, Column::Size, Column::Cost, Column::Name];
+
+// optional sections after the items, each one starts with a line holding its name
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Constraints,
+    Interactions,
+    Bins,
+}
+
+fn parse_section(s: &str) -> Option<Section> {
+    match s.trim().to_lowercase().as_str() {
+        "[constraints]" => Some(Section::Constraints),
+        "[interactions]" => Some(Section::Interactions),
+        "[bins]" => Some(Section::Bins),
+        _ => None,
+    }
+}
+
+fn read_csv<P: AsRef<Path>>(input_file: P) -> DynamicResult<Task> {
     let mut line = String::new();
 
     let file = File::open(input_file)?;
@@ -18,26 +71,247 @@ pub fn read<P: AsRef<Path>>(input_file: P) -> DynamicResult<Task> {
     let mut weights: Vec<Float> = Vec::with_capacity(number_of_objects);
     let mut sizes: Vec<Float> = Vec::with_capacity(number_of_objects);
     let mut costs: Vec<Float> = Vec::with_capacity(number_of_objects);
+    let mut secondary_costs: Vec<Float> = Vec::with_capacity(number_of_objects);
+    let mut names: Vec<Option<String>> = Vec::with_capacity(number_of_objects);
+    let mut groups: Vec<Option<String>> = Vec::with_capacity(number_of_objects);
+    let mut upper_bounds: Vec<Option<Float>> = Vec::with_capacity(number_of_objects);
+    let mut columns: Option<Vec<Column>> = None;
+    // lines of the sections, parsed after the item names are known
+    let mut section: Option<Section> = None;
+    let mut constraint_lines: Vec<String> = Vec::new();
+    let mut interaction_lines: Vec<String> = Vec::new();
+    let mut bin_lines: Vec<String> = Vec::new();
 
     for l in reader.lines() {
-        let parsed_line = parse_string_to_vec(&l?)?;
+        let l = l?;
+        if l.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(next_section) = parse_section(&l) {
+            section = Some(next_section);
+            continue;
+        }
+        match section {
+            Some(Section::Constraints) => {
+                constraint_lines.push(l);
+                continue;
+            }
+            Some(Section::Interactions) => {
+                interaction_lines.push(l);
+                continue;
+            }
+            Some(Section::Bins) => {
+                bin_lines.push(l);
+                continue;
+            }
+            None => {}
+        }
+
+        // the optional header, naming the columns, is the first line after the capacities
+        if columns.is_none() {
+            match parse_header(&l)? {
+                Some(header) => {
+                    columns = Some(header);
+                    continue;
+                }
+                None => columns = Some(DEFAULT_COLUMNS.to_vec()),
+            }
+        }
 
-        weights.push(parsed_line[0] as Float);
-        sizes.push(parsed_line[1] as Float);
-        costs.push(parsed_line[2] as Float);
+        let mut name = None;
+        let mut group = None;
+        // items without a bound are selected at most once
+        let mut upper_bound = Some(1);
+        let mut values = [None; 3];
+        let mut secondary_cost = None;
+
+        for (column, value) in columns.as_ref().unwrap().iter().zip(l.trim().split(',')) {
+            let value = value.trim();
+            match column {
+                Column::Name => name = Some(value.to_string()).filter(|name| !name.is_empty()),
+                Column::Group => group = Some(value.to_string()).filter(|group| !group.is_empty()),
+                Column::Bound if value.eq_ignore_ascii_case(UNBOUNDED) => upper_bound = None,
+                Column::Bound if !value.is_empty() => upper_bound = Some(parse_value(value, &l)?),
+                Column::Bound => {}
+                Column::Weight => values[0] = Some(parse_value(value, &l)?),
+                Column::Size => values[1] = Some(parse_value(value, &l)?),
+                Column::Cost => values[2] = Some(parse_value(value, &l)?),
+                Column::SecondaryCost => secondary_cost = Some(parse_value(value, &l)?),
+            }
+        }
+
+        let has_secondary_cost = columns
+            .as_ref()
+            .is_some_and(|columns| columns.contains(&Column::SecondaryCost));
+        match (secondary_cost, has_secondary_cost) {
+            (Some(secondary_cost), _) => secondary_costs.push(secondary_cost),
+            (None, true) => return Err(format!("missing secondary cost in line: {}", l).into()),
+            (None, false) => {}
+        }
+
+        match values {
+            [Some(weight), Some(size), Some(cost)] => {
+                weights.push(weight);
+                sizes.push(size);
+                costs.push(cost);
+                names.push(name);
+                groups.push(group);
+                upper_bounds.push(upper_bound);
+            }
+            _ => return Err(format!("missing weight, size or cost in line: {}", l).into()),
+        }
     }
     let weights = FloatDVector::from_vec(weights);
     let sizes = FloatDVector::from_vec(sizes);
     let costs = FloatDVector::from_vec(costs);
 
-    Ok(Task::from_args(
+    let mut task = Task::from_args(
         number_of_objects,
         max_weight,
         max_size,
         weights,
         sizes,
         costs,
-    ))
+    );
+
+    if columns
+        .as_ref()
+        .is_some_and(|columns| columns.contains(&Column::SecondaryCost))
+    {
+        task = task.with_secondary_costs(FloatDVector::from_vec(secondary_costs));
+    }
+
+    if names.iter().any(Option::is_some) {
+        let names = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| name.unwrap_or_else(|| i.to_string()))
+            .collect();
+        task = task.with_item_names(names);
+    }
+
+    if columns.is_some_and(|columns| columns.contains(&Column::Bound)) {
+        task = task.with_upper_bounds(upper_bounds);
+    }
+
+    if !bin_lines.is_empty() {
+        if task.get_upper_bounds().is_some() {
+            return Err("items selected more than once cannot be assigned to bins".into());
+        }
+        task = task.with_bins(parse_bins(&bin_lines)?);
+    }
+
+    let mut constraints = parse_constraints(&task, &constraint_lines)?;
+    constraints.groups = Group::from_item_groups(&groups);
+    let interactions = parse_interactions(&task, &interaction_lines)?;
+
+    Ok(task
+        .with_constraints(constraints)
+        .with_interactions(interactions))
+}
+
+fn find_item(task: &Task, reference: &str) -> DynamicResult<usize> {
+    task.find_item(reference)
+        .ok_or_else(|| format!("unknown item: {}", reference).into())
+}
+
+// each line holds the kind of the constraint followed by the names or indices of its items
+fn parse_constraints(task: &Task, lines: &[String]) -> DynamicResult<Constraints> {
+    let mut constraints = Constraints::default();
+
+    for line in lines {
+        let mut fields = line.trim().split(',').map(str::trim);
+        let kind = fields.next().unwrap_or_default().to_lowercase();
+        let items = fields
+            .map(|reference| find_item(task, reference))
+            .collect::<DynamicResult<Vec<usize>>>()?;
+
+        constraints.add(&kind, &items)?;
+    }
+
+    constraints.validate(task.number_of_objects)?;
+    Ok(constraints)
+}
+
+// each line holds the weight and size capacities of a bin, followed by its optional name
+fn parse_bins(lines: &[String]) -> DynamicResult<Vec<Bin>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(number, line)| {
+            let fields: Vec<&str> = line.trim().split(',').map(str::trim).collect();
+            let (max_weight, max_size) = match fields.as_slice() {
+                [max_weight, max_size] | [max_weight, max_size, _] => (max_weight, max_size),
+                _ => {
+                    return Err(
+                        format!("expected the capacities of a bin in line: {}", line).into(),
+                    )
+                }
+            };
+
+            Ok(Bin {
+                name: fields
+                    .get(2)
+                    .map_or_else(|| (number + 1).to_string(), |name| name.to_string()),
+                max_weight: parse_value(max_weight, line)? as usize,
+                max_size: parse_value(max_size, line)? as usize,
+            })
+        })
+        .collect()
+}
+
+// each line holds the names or indices of two items followed by the profit of the pair
+fn parse_interactions(task: &Task, lines: &[String]) -> DynamicResult<Interactions> {
+    let pairs = lines
+        .iter()
+        .map(|line| {
+            let fields: Vec<&str> = line.trim().split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [first, second, profit] => Ok((
+                    find_item(task, first)?,
+                    find_item(task, second)?,
+                    parse_value(profit, line)?,
+                )),
+                _ => Err(format!("expected two items and a profit in line: {}", line).into()),
+            }
+        })
+        .collect::<DynamicResult<Vec<_>>>()?;
+
+    Interactions::new(task.number_of_objects, &pairs)
+}
+
+// returns the columns, if the line is a header, or none, if it already describes an item
+fn parse_header(s: &str) -> DynamicResult<Option<Vec<Column>>> {
+    let fields: Vec<&str> = s.trim().split(',').map(str::trim).collect();
+    if fields.iter().any(|field| field.parse::<f64>().is_ok()) {
+        return Ok(None);
+    }
+
+    let columns = fields
+        .iter()
+        .map(|field| match field.to_lowercase().as_str() {
+            "weight" => Ok(Column::Weight),
+            "size" => Ok(Column::Size),
+            "cost" => Ok(Column::Cost),
+            "name" | "id" => Ok(Column::Name),
+            "group" => Ok(Column::Group),
+            "bound" => Ok(Column::Bound),
+            "secondary-cost" | "secondary_cost" => Ok(Column::SecondaryCost),
+            _ => Err(format!("unknown column: {}", field).into()),
+        })
+        .collect::<DynamicResult<Vec<Column>>>()?;
+
+    Ok(Some(columns))
+}
+
+// the line is quoted in the error messages
+fn parse_value(s: &str, line: &str) -> DynamicResult<Float> {
+    let value = s
+        .parse::<f64>()
+        .map_err(|_| format!("cannot parse {} as f64 in line: {}", s, line))?;
+
+    integral_value(value).map_err(|e| format!("{} in line: {}", e, line).into())
 }
 
 fn get_first_line_variables(s: &str) -> DynamicResult<(usize, usize, usize)> {
@@ -58,13 +332,145 @@ fn get_first_line_variables(s: &str) -> DynamicResult<(usize, usize, usize)> {
     Ok((v[0], v[1], v[2]))
 }
 
-fn parse_string_to_vec(s: &str) -> DynamicResult<Vec<f64>> {
-    s.trim()
-        .split(',')
-        .map(|x| {
-            x.parse::<f64>()
-                // TODO: refactor error
-                .map_err(|_| "cannot parse {} as f64".into())
-        })
-        .collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    // writes the content to a file of the temporary directory, named to be unique within the tests
+    fn file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("loader-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn read_file(name: &str, content: &str) -> DynamicResult<Task> {
+        let path = file(name, content);
+        let task = read(&path);
+        fs::remove_file(&path).unwrap();
+        task
+    }
+
+    fn summary(task: &Task) -> Vec<(String, Float, Float, Float)> {
+        (0..task.number_of_objects)
+            .map(|item| {
+                (
+                    task.get_item_name(item),
+                    task.get_weights()[item],
+                    task.get_sizes()[item],
+                    task.get_costs()[item],
+                )
+            })
+            .collect()
+    }
+
+    const CSV: &str = "3,10,20
+name,weight,size,cost,group
+tent,4,8,30,shelter
+tarp,2,3,10,shelter
+stove,3,5,20,
+[constraints]
+requires,stove,tent
+[interactions]
+tent,stove,7
+[bins]
+6,10,left
+4,10,right
+";
+
+    const JSON: &str = r#"{
+        "resources": [{"name": "weight", "capacity": 10}, {"name": "size", "capacity": 20}],
+        "items": [
+            {"id": "tent", "group": "shelter", "cost": 30, "usage": [4, 8]},
+            {"id": "tarp", "group": "shelter", "cost": 10, "usage": [2, 3]},
+            {"id": "stove", "cost": 20, "usage": [3, 5]}
+        ],
+        "constraints": {"requires": [["stove", "tent"]]},
+        "interactions": [{"items": ["tent", "stove"], "profit": 7}],
+        "bins": [{"name": "left", "capacity": [6, 10]}, {"name": "right", "capacity": [4, 10]}]
+    }"#;
+
+    #[test]
+    fn csv_json_and_yaml_describe_the_same_task() {
+        let csv = read_file("same.csv", CSV).unwrap();
+        let json = read_file("same.json", JSON).unwrap();
+        let instance: Instance = serde_json::from_str(JSON).unwrap();
+        let yaml = read_file("same.yaml", &serde_yaml::to_string(&instance).unwrap()).unwrap();
+
+        for task in &[json, yaml] {
+            assert_eq!(summary(task), summary(&csv));
+            assert_eq!(
+                (task.max_weight, task.max_size),
+                (csv.max_weight, csv.max_size)
+            );
+            let bins = |task: &Task| -> Vec<(String, usize, usize)> {
+                task.get_bins()
+                    .unwrap()
+                    .iter()
+                    .map(|bin| (bin.name.clone(), bin.max_weight, bin.max_size))
+                    .collect()
+            };
+            assert_eq!(bins(task), bins(&csv));
+            let groups = |task: &Task| -> Vec<(String, Vec<usize>)> {
+                task.get_constraints()
+                    .groups
+                    .iter()
+                    .map(|group| (group.name.clone(), group.items.clone()))
+                    .collect()
+            };
+            assert_eq!(groups(task), groups(&csv));
+            // the stove without the tent breaks the requirement
+            let selected = |item| item != 0;
+            assert_eq!(task.get_constraints().violations(selected).total(), 1);
+            assert_eq!(csv.get_constraints().violations(selected).total(), 1);
+            assert_eq!(
+                task.get_interactions().profit(|_| 1),
+                csv.get_interactions().profit(|_| 1)
+            );
+        }
+    }
+
+    #[test]
+    fn items_without_a_name_are_named_by_their_index() {
+        let task = read_file("unnamed.csv", "3,10,10\n1,2,3,first\n4,5,6\n7,8,9,third\n").unwrap();
+
+        assert_eq!(
+            summary(&task),
+            vec![
+                ("first".to_string(), 1, 2, 3),
+                ("1".to_string(), 4, 5, 6),
+                ("third".to_string(), 7, 8, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn header_orders_the_columns() {
+        let task = read_file(
+            "header.csv",
+            "2,10,10\ncost,secondary-cost,id,size,weight\n3,1,a,2,1\n6,4,b,5,4\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary(&task),
+            vec![("a".to_string(), 1, 2, 3), ("b".to_string(), 4, 5, 6)]
+        );
+        assert_eq!(
+            task.get_secondary_costs().unwrap(),
+            &FloatDVector::from_vec(vec![1, 4])
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert!(read_file("fraction.csv", "1,10,10\n1.5,2,3\n").is_err());
+        assert!(read_file("missing.csv", "1,10,10\n1,2\n").is_err());
+        assert!(read_file("column.csv", "1,10,10\nweight,volume,cost\n1,2,3\n").is_err());
+        assert!(read_file(
+            "unknown.csv",
+            "1,10,10\n1,2,3,a\n[constraints]\nforbidden,b\n"
+        )
+        .is_err());
+    }
 }
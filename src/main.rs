@@ -1,18 +1,14 @@
-#[macro_use]
-extern crate structopt_derive;
-
-use crate::data_structures::Float;
 use structopt::StructOpt;
 
-mod algorithm;
-mod data_structures;
-mod generator;
-mod loader;
-mod opt;
-
-use crate::data_structures::population::Population;
-use crate::data_structures::{task::Task, DynamicResult};
-use opt::Opt;
+use genetic::data_structures::population::Population;
+use genetic::data_structures::{
+    statistics::write_statistics, task::Task, DynamicResult, Float, Random,
+};
+use genetic::{
+    algorithm, bounds::References, exact, experiment, generator, loader, nsga, opt::Opt,
+};
+use rand::SeedableRng;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::{fs::File, io::Write, time::Instant};
 
@@ -34,18 +30,27 @@ fn main() -> DynamicResult<()> {
                 Err(e) => eprintln!("Error: {}", e),
             }
         }
-        Opt::Init(config) => {
+        Opt::Init(mut config) => {
             let start = Instant::now();
+            config.resolve_legacy_density()?;
 
             match loader::read(config.file_name.unwrap_or_else(|| "tasks.csv".to_string())) {
                 Ok(task) => {
                     let time = start.elapsed().as_secs_f32();
                     println!("{}", time);
-                    let population = Population::generate_initial_population(
+                    let ones_probability = config.density.ones_probability(&task)?;
+                    println!("ones probability: {}", ones_probability);
+                    let mut rng = Random::from_entropy();
+                    let mut population = Population::generate_initial_population(
                         config.size,
                         task.number_of_objects,
-                        config.density,
+                        ones_probability,
+                        &mut rng,
                     );
+                    if let Some(gene_bounds) = task.get_gene_bounds() {
+                        population.draw_copies(gene_bounds, &mut rng);
+                    }
+                    population.repair(task.get_constraints(), &mut rng);
                     let time = start.elapsed().as_secs_f32();
 
                     println!("{}", time);
@@ -55,23 +60,88 @@ fn main() -> DynamicResult<()> {
                 Err(e) => println!("{}", e),
             }
         }
-        Opt::Evolve(config) => match algorithm::evolve(&config) {
-            Ok(results) => {
-                println!("{:?}", &results.iter().cloned().collect::<Vec<Float>>());
+        Opt::Evolve(config) => match loader::read(
+            config
+                .input_file
+                .as_ref()
+                .unwrap_or(&PathBuf::from("tasks.csv")),
+        )
+        .and_then(|task| {
+            // the error cannot be sent from the thread pool, so it is passed as a message
+            let evolution = config
+                .execution
+                .thread_pool()?
+                .install(|| algorithm::evolve(&config, &task).map_err(|e| e.to_string()))?;
+            Ok((evolution, task))
+        }) {
+            Ok((evolution, task)) => {
+                println!("seed: {}", evolution.seed);
+                for event in evolution.restarts.iter() {
+                    println!("{}", event);
+                }
+                let hit_rates: Vec<f64> = evolution
+                    .statistics
+                    .iter()
+                    .filter_map(|s| s.cache_hit_rate)
+                    .collect();
+                if !hit_rates.is_empty() {
+                    let mean = hit_rates.iter().sum::<f64>() / hit_rates.len() as f64;
+                    println!("cache hit rate: {:.3}", mean);
+                }
+                if let Some(statistics_file) = &config.statistics_file {
+                    write_statistics(statistics_file, &evolution.statistics)?;
+                }
+
+                let results: Vec<Float> = evolution.statistics.iter().map(|s| s.best).collect();
+                println!("{:?}", &results);
                 let mut output_file = File::create(&config.output_file).unwrap();
                 results
                     .iter()
                     .for_each(|x| output_file.write_fmt(format_args!("{}\n", x)).unwrap());
+                // the last generation is evaluated after its statistics,
+                // so the best individual can be better than all of them
+                let best = evolution.best_fitness;
+                println!("best individual: {}", best);
+
+                if let Some(solution) = &evolution.best {
+                    solution.write_report(&task, &mut std::io::stdout())?;
+                    if let Some(solution_file) = &config.solution_file {
+                        solution.write_report(&task, &mut File::create(solution_file)?)?;
+                    }
+                }
+
+                let references = References::compute(&task);
+                match references.greedy {
+                    Some(greedy) => println!("greedy solution: {}", greedy),
+                    None => println!("greedy solution: violates the side constraints"),
+                }
+                println!("upper bound (LP relaxation): {:.2}", references.upper_bound);
                 println!(
-                    "best individual: {}",
-                    &results
-                        .iter()
-                        .max()
-                        .expect("no populations has been raised")
-                )
+                    "optimality gap: {:.2}%",
+                    references.optimality_gap(best as u64)
+                );
+                if let Some(optimum) = task.known_optimum {
+                    println!(
+                        "gap to the known optimum {}: {:.2}%",
+                        optimum,
+                        100f64 * (optimum as f64 - best as f64) / optimum.max(1) as f64
+                    );
+                }
             }
             Err(err) => eprint!("{}", err),
         },
+        Opt::SolveExact(config) => match exact::solve_exact(config) {
+            Ok(_) => {}
+            Err(e) => eprintln!("Error: {}", e),
+        },
+        Opt::Pareto(config) => match nsga::pareto(config) {
+            Ok(_) => {}
+            Err(e) => eprintln!("Error: {}", e),
+        },
+        Opt::Experiment(config) => match experiment::experiment(config) {
+            Ok(_) => {}
+            Err(e) => eprintln!("Error: {}", e),
+        },
     }
 
     Ok(())
@@ -0,0 +1,461 @@
+use super::opt::Pareto as ParetoConfig;
+use crate::data_structures::{
+    execution::derived_rng, population::Population, task::Task, DynamicResult, FloatDVector,
+    PopulationMatrix, Random,
+};
+use crate::loader;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    /// total cost of the selected items
+    Cost,
+    /// capacity of the knapsack, that is left unused by the weight of the selected items
+    WeightSlack,
+    /// capacity of the knapsack, that is left unused by the size of the selected items
+    SizeSlack,
+    /// total secondary cost of the selected items, the instance has to provide it
+    SecondaryCost,
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cost" => Ok(Objective::Cost),
+            "weight-slack" => Ok(Objective::WeightSlack),
+            "size-slack" => Ok(Objective::SizeSlack),
+            "secondary-cost" => Ok(Objective::SecondaryCost),
+            _ => Err(format!(
+                "unknown objective: {}, expected one of: cost, weight-slack, size-slack, \
+                 secondary-cost",
+                s
+            )),
+        }
+    }
+}
+
+// objective values of a single individual, all of them are maximized
+#[derive(Debug, Clone)]
+struct Evaluation {
+    objectives: Vec<f64>,
+    // relative excess over the capacities plus the number of the violated constraints,
+    // zero for feasible individuals
+    violation: f64,
+    rank: usize,
+    crowding_distance: f64,
+}
+
+impl Evaluation {
+    // constrained domination: feasible individuals dominate the infeasible ones,
+    // and infeasible ones are compared by their violation
+    fn dominates(&self, other: &Evaluation) -> bool {
+        if self.violation > 0f64 || other.violation > 0f64 {
+            return self.violation < other.violation;
+        }
+
+        self.objectives
+            .iter()
+            .zip(other.objectives.iter())
+            .all(|(a, b)| a >= b)
+            && self
+                .objectives
+                .iter()
+                .zip(other.objectives.iter())
+                .any(|(a, b)| a > b)
+    }
+
+    // crowded comparison operator, the lower rank and then the higher distance wins
+    fn crowded_cmp(&self, other: &Evaluation) -> Ordering {
+        other.rank.cmp(&self.rank).then(
+            self.crowding_distance
+                .partial_cmp(&other.crowding_distance)
+                .unwrap_or(Ordering::Equal),
+        )
+    }
+}
+
+/// Item set on the Pareto front, together with its resource usage.
+#[derive(Debug, Clone)]
+pub struct ParetoSolution {
+    pub items: Vec<usize>,
+    pub cost: u64,
+    /// none, if the instance has no secondary costs
+    pub secondary_cost: Option<u64>,
+    pub weight: u64,
+    pub size: u64,
+}
+
+pub fn pareto(config: ParetoConfig) -> DynamicResult<()> {
+    let task = loader::read(config.file_name.as_deref().unwrap_or("tasks.csv"))?;
+    // the error cannot be sent from the thread pool, so it is passed as a message
+    let front = config
+        .execution
+        .thread_pool()?
+        .install(|| evolve_pareto_front(&config, &task).map_err(|e| e.to_string()))?;
+
+    println!("solutions on the Pareto front: {}", front.len());
+
+    let mut file = File::create(&config.output_file)?;
+    let secondary_costs = task.get_secondary_costs().is_some();
+    if secondary_costs {
+        file.write_fmt(format_args!("cost,secondary_cost,weight,size,items\n"))?;
+    } else {
+        file.write_fmt(format_args!("cost,weight,size,items\n"))?;
+    }
+    for solution in front.iter() {
+        let items: Vec<String> = solution.items.iter().map(|i| i.to_string()).collect();
+        if let Some(secondary_cost) = solution.secondary_cost {
+            file.write_fmt(format_args!("{},{},", solution.cost, secondary_cost))?;
+        } else {
+            file.write_fmt(format_args!("{},", solution.cost))?;
+        }
+        file.write_fmt(format_args!(
+            "{},{},{}\n",
+            solution.weight,
+            solution.size,
+            items.join(" ")
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Runs NSGA-II and returns the feasible, non dominated solutions of the last population.
+pub fn evolve_pareto_front(
+    config: &ParetoConfig,
+    task: &Task,
+) -> DynamicResult<Vec<ParetoSolution>> {
+    if config.population_size < 2 {
+        return Err("population size has to be at least 2".into());
+    }
+    if config.objectives.is_empty() {
+        return Err("at least one objective has to be given".into());
+    }
+    if task.get_gene_bounds().is_some() {
+        return Err(
+            "pareto supports only a single knapsack with items selected at most once".into(),
+        );
+    }
+    if config.objectives.contains(&Objective::SecondaryCost) && task.get_secondary_costs().is_none()
+    {
+        return Err("objective secondary-cost needs the secondary costs of the items".into());
+    }
+
+    let size = config.population_size;
+    let mut rng = derived_rng(config.execution.seed(), 0);
+    let mut parents = Population::generate_initial_population(
+        size,
+        task.number_of_objects,
+        config.density.ones_probability(task)?,
+        &mut rng,
+    );
+    parents.set_parallel(!config.execution.sequential);
+    parents.set_evaluation_backend(config.execution.evaluation);
+    parents.repair(task.get_constraints(), &mut rng);
+    let mut parents_evaluation = evaluate(&parents, task, &config.objectives);
+    assign_ranks_and_distances(&mut parents_evaluation);
+
+    let mut offspring = Population::from(PopulationMatrix::zeros(task.number_of_objects, size));
+    offspring.set_parallel(!config.execution.sequential);
+    offspring.set_evaluation_backend(config.execution.evaluation);
+
+    for _ in 0..config.iterations {
+        offspring.breed_from(
+            &parents,
+            |rng| binary_tournament(&parents_evaluation, rng),
+            config.crossover_rate,
+            config.mutation_rate,
+            task.get_constraints(),
+            &mut rng,
+        );
+
+        let mut combined = parents_evaluation;
+        combined.extend(evaluate(&offspring, task, &config.objectives));
+        assign_ranks_and_distances(&mut combined);
+
+        // the best ranks survive, ties on the last front are broken by the crowding distance
+        let mut order: Vec<usize> = (0..combined.len()).collect();
+        order.sort_by(|&a, &b| combined[b].crowded_cmp(&combined[a]));
+        order.truncate(size);
+
+        parents = Population::select_from(&parents, &offspring, &order);
+        parents_evaluation = order.iter().map(|&i| combined[i].clone()).collect();
+        // the crowding distances have to be computed again for the survivors
+        assign_ranks_and_distances(&mut parents_evaluation);
+    }
+
+    let (weights, sizes, costs) = parents.resource_sums(task);
+    let secondary_costs = parents.secondary_cost_sums(task);
+    let mut front: Vec<ParetoSolution> = parents_evaluation
+        .iter()
+        .enumerate()
+        .filter(|(_, evaluation)| evaluation.rank == 0 && evaluation.violation == 0f64)
+        .map(|(i, _)| ParetoSolution {
+            items: parents.selected_items(i),
+            cost: costs[i] as u64,
+            secondary_cost: secondary_costs.as_ref().map(|sums| sums[i] as u64),
+            weight: weights[i] as u64,
+            size: sizes[i] as u64,
+        })
+        .collect();
+
+    front.sort_by(|a, b| b.cost.cmp(&a.cost).then(a.items.cmp(&b.items)));
+    front.dedup_by(|a, b| a.items == b.items);
+
+    Ok(front)
+}
+
+fn evaluate(population: &Population, task: &Task, objectives: &[Objective]) -> Vec<Evaluation> {
+    let (weights, sizes, costs) = population.resource_sums(task);
+    // the objective is rejected, unless the task has the secondary costs
+    let secondary_costs = population
+        .secondary_cost_sums(task)
+        .unwrap_or_else(|| FloatDVector::zeros(population.number_of_individuals()));
+    let violations = population.violations(task.get_constraints());
+    let max_weight = task.max_weight as f64;
+    let max_size = task.max_size as f64;
+    let excess = |sum: &FloatDVector, i: usize, capacity: f64| {
+        (sum[i] as f64 - capacity).max(0f64) / capacity.max(1f64)
+    };
+
+    (0..population.number_of_individuals())
+        .map(|i| Evaluation {
+            objectives: objectives
+                .iter()
+                .map(|objective| match objective {
+                    Objective::Cost => costs[i] as f64,
+                    Objective::WeightSlack => max_weight - weights[i] as f64,
+                    Objective::SizeSlack => max_size - sizes[i] as f64,
+                    Objective::SecondaryCost => secondary_costs[i] as f64,
+                })
+                .collect(),
+            violation: excess(&weights, i, max_weight)
+                + excess(&sizes, i, max_size)
+                + violations[i].total() as f64,
+            rank: 0,
+            crowding_distance: 0f64,
+        })
+        .collect()
+}
+
+fn binary_tournament(evaluation: &[Evaluation], rng: &mut Random) -> usize {
+    let first = rng.gen_range(0, evaluation.len());
+    let second = rng.gen_range(0, evaluation.len());
+
+    match evaluation[first].crowded_cmp(&evaluation[second]) {
+        Ordering::Less => second,
+        _ => first,
+    }
+}
+
+// fast non dominated sorting, followed by the crowding distance within each front
+fn assign_ranks_and_distances(evaluation: &mut [Evaluation]) {
+    let n = evaluation.len();
+    let mut dominated: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut domination_count = vec![0usize; n];
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if evaluation[i].dominates(&evaluation[j]) {
+                dominated[i].push(j);
+                domination_count[j] += 1;
+            } else if evaluation[j].dominates(&evaluation[i]) {
+                dominated[j].push(i);
+                domination_count[i] += 1;
+            }
+        }
+    }
+
+    let mut front: Vec<usize> = (0..n).filter(|&i| domination_count[i] == 0).collect();
+    let mut rank = 0;
+
+    while !front.is_empty() {
+        let mut next_front = Vec::new();
+
+        for &i in front.iter() {
+            evaluation[i].rank = rank;
+            for &j in dominated[i].iter() {
+                domination_count[j] -= 1;
+                if domination_count[j] == 0 {
+                    next_front.push(j);
+                }
+            }
+        }
+
+        assign_crowding_distances(evaluation, &front);
+        front = next_front;
+        rank += 1;
+    }
+}
+
+fn assign_crowding_distances(evaluation: &mut [Evaluation], front: &[usize]) {
+    front
+        .iter()
+        .for_each(|&i| evaluation[i].crowding_distance = 0f64);
+
+    let number_of_objectives = front.first().map_or(0, |&i| evaluation[i].objectives.len());
+    let mut sorted = front.to_vec();
+
+    for objective in 0..number_of_objectives {
+        sorted.sort_by(|&a, &b| {
+            evaluation[a].objectives[objective]
+                .partial_cmp(&evaluation[b].objectives[objective])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let (first, last) = (sorted[0], sorted[sorted.len() - 1]);
+        let range =
+            evaluation[last].objectives[objective] - evaluation[first].objectives[objective];
+        evaluation[first].crowding_distance = f64::INFINITY;
+        evaluation[last].crowding_distance = f64::INFINITY;
+
+        if range <= 0f64 {
+            continue;
+        }
+
+        for k in 1..sorted.len().saturating_sub(1) {
+            let distance = (evaluation[sorted[k + 1]].objectives[objective]
+                - evaluation[sorted[k - 1]].objectives[objective])
+                / range;
+            evaluation[sorted[k]].crowding_distance += distance;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::constraints::Constraints;
+    use crate::fixtures::random_task;
+    use structopt::StructOpt;
+
+    const ITEMS: usize = 25;
+
+    fn evaluation(objectives: &[f64], violation: f64) -> Evaluation {
+        Evaluation {
+            objectives: objectives.to_vec(),
+            violation,
+            rank: 0,
+            crowding_distance: 0f64,
+        }
+    }
+
+    fn config(population_size: usize) -> ParetoConfig {
+        let population_size = population_size.to_string();
+        ParetoConfig::from_iter_safe(&[
+            "pareto",
+            "--iterations",
+            "40",
+            "--population-size",
+            &population_size,
+            "--crossover-rate",
+            "0.9",
+            "--mutation-rate",
+            "0.05",
+            "--seed",
+            "31",
+            "--output",
+            "front.csv",
+        ])
+        .unwrap()
+    }
+
+    fn constrained_task(rng: &mut Random) -> Task {
+        let mut constraints = Constraints::default();
+        constraints.add("conflict", &[0, 1]).unwrap();
+        constraints.add("forbidden", &[2]).unwrap();
+
+        random_task(ITEMS, 1..50, 0.5, rng).with_constraints(constraints)
+    }
+
+    #[test]
+    fn feasible_individuals_dominate_by_all_objectives() {
+        let a = evaluation(&[3.0, 2.0], 0f64);
+        let b = evaluation(&[3.0, 1.0], 0f64);
+        let c = evaluation(&[1.0, 4.0], 0f64);
+
+        assert!(a.dominates(&b));
+        assert!(!b.dominates(&a));
+        assert!(!a.dominates(&c) && !c.dominates(&a));
+        assert!(!a.dominates(&a));
+    }
+
+    #[test]
+    fn smaller_violation_dominates() {
+        let feasible = evaluation(&[0.0, 0.0], 0f64);
+        let slightly = evaluation(&[9.0, 9.0], 0.5);
+        let heavily = evaluation(&[9.0, 9.0], 2.0);
+
+        assert!(feasible.dominates(&slightly));
+        assert!(slightly.dominates(&heavily));
+        assert!(!slightly.dominates(&feasible));
+        assert!(!heavily.dominates(&slightly));
+    }
+
+    #[test]
+    fn ranks_and_distances_of_a_hand_built_front() {
+        let mut evaluation = vec![
+            evaluation(&[4.0, 1.0], 0f64),
+            evaluation(&[3.0, 2.0], 0f64),
+            evaluation(&[1.0, 4.0], 0f64),
+            evaluation(&[2.0, 1.0], 0f64),
+            evaluation(&[0.0, 0.0], 0f64),
+            evaluation(&[9.0, 9.0], 1.0),
+        ];
+        assign_ranks_and_distances(&mut evaluation);
+
+        let ranks: Vec<usize> = evaluation.iter().map(|e| e.rank).collect();
+        assert_eq!(ranks, vec![0, 0, 0, 1, 2, 3]);
+
+        // the extremes of each objective get an infinite distance, the middle one the sum
+        // of the normalized distances of its neighbours in both objectives
+        let distances: Vec<f64> = evaluation.iter().map(|e| e.crowding_distance).collect();
+        assert!(distances[0].is_infinite() && distances[2].is_infinite());
+        assert!((distances[1] - (3.0 / 3.0 + 3.0 / 3.0)).abs() < 1e-9);
+        assert!(distances[3].is_infinite() && distances[4].is_infinite());
+    }
+
+    #[test]
+    fn small_population_is_rejected() {
+        let task = constrained_task(&mut derived_rng(31, 0));
+        assert!(evolve_pareto_front(&config(1), &task).is_err());
+        assert!(evolve_pareto_front(&config(2), &task).is_ok());
+    }
+
+    #[test]
+    fn front_is_feasible_and_non_dominated() {
+        let task = constrained_task(&mut derived_rng(31, 0));
+        let front = evolve_pareto_front(&config(30), &task).unwrap();
+        assert!(!front.is_empty());
+
+        for solution in front.iter() {
+            assert!(solution.weight <= task.max_weight as u64);
+            assert!(solution.size <= task.max_size as u64);
+            let selected = |item: usize| solution.items.binary_search(&item).is_ok();
+            assert_eq!(task.get_constraints().violations(selected).total(), 0);
+        }
+
+        // the default objectives: the cost and both slacks
+        let objectives = |solution: &ParetoSolution| {
+            evaluation(
+                &[
+                    solution.cost as f64,
+                    (task.max_weight as u64 - solution.weight) as f64,
+                    (task.max_size as u64 - solution.size) as f64,
+                ],
+                0f64,
+            )
+        };
+        for a in front.iter() {
+            assert!(front
+                .iter()
+                .all(|b| !objectives(b).dominates(&objectives(a))));
+        }
+    }
+}
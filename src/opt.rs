@@ -1,4 +1,10 @@
 use crate::data_structures::algorithm_configuration::AlgorithmConfiguration as Configuration;
+use crate::data_structures::density::DensityOptions;
+use crate::data_structures::execution::ExecutionOptions;
+use crate::data_structures::DynamicResult;
+use crate::generator::InstanceClass;
+use crate::nsga::Objective;
+use std::path::PathBuf;
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -6,7 +12,8 @@ use crate::data_structures::algorithm_configuration::AlgorithmConfiguration as C
     about = "A laboratory task for Introduction to Artificial Intelligence"
 )]
 pub enum Opt {
-    /// Generates csv file with random weight, size and cost values for n entities
+    /// Generates csv file with random weight, size and cost values for n entities.
+    /// If the output file has the json, yaml or yml extension, the instance is stored in that format
     Generate(Generate),
 
     /// Reads values from file, parses it and creates `Task` instance. Generally, use just for debugging purposes!
@@ -17,6 +24,17 @@ pub enum Opt {
 
     /// Run the algorithm
     Evolve(Configuration),
+
+    /// Finds the optimal solution with dynamic programming or branch and bound,
+    /// use to validate the results of the algorithm on small and medium tasks
+    SolveExact(SolveExact),
+
+    /// Run NSGA-II, searching for the Pareto front of the total cost and the unused capacities
+    Pareto(Pareto),
+
+    /// Runs the algorithm several times with different seeds, spreading the independent trials
+    /// across the threads, and stores the mean best individual of each generation
+    Experiment(Experiment),
 }
 
 #[derive(StructOpt, Debug)]
@@ -30,16 +48,33 @@ pub struct Generate {
     /// max size of the knapsack   
     pub max_size: usize,
 
-    /// generation multiplier
+    /// generation multiplier, the weights and sizes are drawn from the range
+    /// [1, multiplier * capacity / number of items]
     pub multiplier: usize,
 
     /// Output file for the generated values
     pub output_file: Option<String>,
+
+    /// correlation between the costs and the resources of the items.
+    /// One of: uncorrelated, weakly-correlated, strongly-correlated, inverse-strongly-correlated,
+    /// almost-strongly-correlated, subset-sum, spanner
+    #[structopt(long, default_value = "uncorrelated")]
+    pub class: InstanceClass,
+
+    /// if set, the capacities are this fraction of the total weight and size of the items,
+    /// instead of `max-weight` and `max-size`. Should be set in range (0, 1)
+    #[structopt(long)]
+    pub tightness: Option<f64>,
+
+    /// seed of the random number generator, the same seed and arguments
+    /// always generate the same instance
+    #[structopt(long)]
+    pub seed: Option<u64>,
 }
 
 #[derive(StructOpt, Debug)]
 pub struct Read {
-    /// path to CSV file generated by the `Generate` sub command
+    /// path to the CSV, JSON or YAML file generated by the `Generate` sub command
     pub file_name: Option<String>,
 }
 
@@ -48,11 +83,132 @@ pub struct Init {
     /// The size of the population
     pub size: usize,
 
-    /// The density of the population - defines, statistically,
-    /// how many ones will be in the initial population
-    /// the higher the value, the lower the density
-    pub density: usize,
+    /// Deprecated, use --density instead. The density of the population,
+    /// the higher the value, the lower the density.
+    /// Without the file argument, it is read as the file, unless it is a number
+    #[structopt(name = "legacy-density")]
+    pub legacy_density: Option<String>,
+
+    // The density of the population - defines, statistically,
+    // how many ones will be in the initial population.
+    // The doc comment of a flattened struct would replace the help of the sub commands,
+    // so the flattened options are described by plain comments.
+    #[structopt(flatten)]
+    pub density: DensityOptions,
 
     /// File with generated tasks
     pub file_name: Option<String>,
 }
+
+impl Init {
+    /// Moves the deprecated positional density into the density options,
+    /// so both `init 100 10 tasks.csv` and `init 100 tasks.csv` keep working.
+    pub fn resolve_legacy_density(&mut self) -> DynamicResult<()> {
+        let legacy_density = match self.legacy_density.take() {
+            Some(legacy_density) => legacy_density,
+            None => return Ok(()),
+        };
+
+        match (legacy_density.parse::<usize>(), self.file_name.is_some()) {
+            (Ok(_), _)
+                if self.density.ones_probability.is_some()
+                    || self.density.expected_items.is_some()
+                    || self.density.density.is_some() =>
+            {
+                return Err("the positional density cannot be used with the density options".into())
+            }
+            (Ok(density), _) => {
+                eprintln!("the positional density is deprecated, use --density instead");
+                self.density.density = Some(density);
+            }
+            (Err(_), true) => {
+                return Err(format!("cannot parse density {} as usize", legacy_density).into())
+            }
+            (Err(_), false) => self.file_name = Some(legacy_density),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SolveExact {
+    /// File with generated tasks
+    pub file_name: Option<String>,
+
+    /// time limit of the branch and bound search, in seconds.
+    /// If it is reached, the best solution found so far is printed
+    #[structopt(short, long, default_value = "60")]
+    pub time_limit: f64,
+
+    /// use branch and bound, even if the task is small enough for dynamic programming
+    #[structopt(long)]
+    pub branch_and_bound: bool,
+
+    /// file to store the value and indices of the selected items in
+    #[structopt(long = "output", parse(from_os_str))]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Pareto {
+    /// number of iterations of the algorithm
+    #[structopt(short, long)]
+    pub iterations: usize,
+
+    /// population size
+    #[structopt(short = "p", long)]
+    pub population_size: usize,
+
+    /// crossover rate, should be set in range [0, 1]
+    #[structopt(short = "c", long)]
+    pub crossover_rate: f64,
+
+    /// mutation rate, should be set in range [0, 1)
+    #[structopt(short = "m", long)]
+    pub mutation_rate: f64,
+
+    /// comma separated list of the maximized objectives.
+    /// Available: cost, weight-slack, size-slack, secondary-cost
+    #[structopt(
+        long,
+        use_delimiter = true,
+        default_value = "cost,weight-slack,size-slack"
+    )]
+    pub objectives: Vec<Objective>,
+
+    // The density of the initial population
+    #[structopt(flatten)]
+    pub density: DensityOptions,
+
+    // The number of threads and the seed of the random number generator
+    #[structopt(flatten)]
+    pub execution: ExecutionOptions,
+
+    /// File with generated tasks
+    #[structopt(long = "input")]
+    pub file_name: Option<String>,
+
+    /// csv file to store the Pareto front in
+    #[structopt(long = "output", parse(from_os_str))]
+    pub output_file: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Experiment {
+    /// number of independent runs of the algorithm, the seed of each run is
+    /// the seed of the experiment increased by the number of the run
+    #[structopt(long)]
+    pub trials: usize,
+
+    /// csv file to store the seed and the best individual of each run in
+    #[structopt(long = "results", parse(from_os_str))]
+    pub results_file: Option<PathBuf>,
+
+    // The parameters of each run
+    #[structopt(flatten)]
+    pub algorithm: Configuration,
+}